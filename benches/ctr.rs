@@ -0,0 +1,64 @@
+//! Counter mode against the plain keystream, and the cost of a seek.
+//!
+//! ```text
+//! cargo bench --bench ctr --features parallel
+//! ```
+//!
+//! One block per call runs every block alone, which shows the cost of starting a block.
+//! Larger calls run four blocks at a time through the lanes.
+
+use spritz_cipher::{SpritzCipher, SpritzCtr};
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LEN: usize = 1 << 24;
+const READS: usize = 1000;
+
+/// The best time of a few runs of `run`.
+fn best(mut run: impl FnMut()) -> Duration {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let mut buf = vec![0u8; LEN];
+    let mb_s = |time: Duration| LEN as f64 / time.as_secs_f64() / 1e6;
+
+    let mut cipher = SpritzCipher::with_nonce(b"key", b"nonce");
+    let plain = mb_s(best(|| cipher.apply_keystream(black_box(&mut buf))));
+
+    let mut ctr = SpritzCtr::new(b"key", b"nonce");
+    let one_block = mb_s(best(|| {
+        for block in buf.chunks_mut(SpritzCtr::BLOCK_LEN) {
+            ctr.apply_keystream(black_box(block)).unwrap();
+        }
+    }));
+    let lanes = mb_s(best(|| ctr.apply_keystream(black_box(&mut buf)).unwrap()));
+
+    println!("{:>24} {:>10}", "", "MB/s");
+    println!("{:>24} {:>10.1}", "SpritzCipher", plain);
+    println!("{:>24} {:>10.1}", "SpritzCtr, block a call", one_block);
+    println!("{:>24} {:>10.1}", "SpritzCtr", lanes);
+    #[cfg(feature = "parallel")]
+    {
+        let parallel = mb_s(best(|| ctr.apply_keystream_parallel(black_box(&mut buf)).unwrap()));
+        println!("{:>24} {:>10.1}  ({} threads)", "apply_keystream_parallel", parallel, rayon::current_num_threads());
+    }
+
+    /* 100 byte reads at scattered positions, each one a seek */
+    let mut read = [0u8; 100];
+    let time = best(|| {
+        for n in 0..READS {
+            ctr.seek((n as u64 * 7919 * 4099) % LEN as u64);
+            ctr.apply_keystream(black_box(&mut read)).unwrap();
+        }
+    });
+    println!("{:>24} {:>10.1} us", "seek and read 100 bytes", time.as_secs_f64() * 1e6 / READS as f64);
+}
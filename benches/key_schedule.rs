@@ -0,0 +1,52 @@
+//! Per-packet cost of keying with `SpritzCipher::with_nonce` against a `SpritzKeySchedule`.
+//!
+//! ```text
+//! cargo bench --bench key_schedule
+//! ```
+
+use spritz_cipher::{SpritzCipher, SpritzKeySchedule};
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const PACKETS: u64 = 20_000;
+const KEY_SIZES: &[usize] = &[16, 32, 64];
+const PACKET_SIZES: &[usize] = &[20, 40, 60];
+
+/// Nanoseconds per packet of `encrypt`, the best of a few runs.
+fn time(mut encrypt: impl FnMut(u64, &mut [u8]), packet_size: usize) -> f64 {
+    let mut packet = vec![0u8; packet_size];
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        for counter in 0..PACKETS {
+            encrypt(counter, &mut packet);
+            black_box(&packet);
+        }
+        best = best.min(start.elapsed());
+    }
+    best.as_nanos() as f64 / PACKETS as f64
+}
+
+fn main() {
+    println!("{:>8} {:>8} {:>14} {:>14} {:>8}", "key", "packet", "with_nonce ns", "schedule ns", "speedup");
+    for &key_size in KEY_SIZES {
+        let key: Vec<u8> = (0..key_size as u8).collect();
+        let schedule = SpritzKeySchedule::new(&key);
+
+        for &packet_size in PACKET_SIZES {
+            let with_nonce = time(
+                |counter, packet| SpritzCipher::with_nonce(black_box(&key), &counter.to_le_bytes()).apply_keystream(packet),
+                packet_size,
+            );
+            let scheduled = time(
+                |counter, packet| schedule.cipher(&counter.to_le_bytes()).apply_keystream(packet),
+                packet_size,
+            );
+            println!(
+                "{:>8} {:>8} {:>14.0} {:>14.0} {:>7.2}x",
+                key_size, packet_size, with_nonce, scheduled, with_nonce / scheduled
+            );
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Short sessions and bulk keystream, one state at a time against `CipherLanes`.
+//!
+//! ```text
+//! cargo bench --bench lanes
+//! cargo bench --bench lanes --features avx2
+//! ```
+
+use spritz_cipher::lanes::{self, CipherLanes};
+use spritz_cipher::{SpritzCipher, SpritzHasher};
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SESSIONS: usize = 4096;
+const PACKET: usize = 40;
+const BULK: usize = 1 << 16;
+
+/// The best time of a few runs of `run`.
+fn best(mut run: impl FnMut()) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn keys() -> Vec<[u8; 32]> {
+    (0..SESSIONS).map(|n| [n as u8; 32]).collect()
+}
+
+/// Sessions per second, each keyed with a nonce and encrypting one packet.
+fn sessions<const L: usize>() -> f64 {
+    let keys = keys();
+    let mut packets = vec![[0u8; PACKET]; SESSIONS];
+    let time = best(|| {
+        for (keys, packets) in keys.chunks_exact(L).zip(packets.chunks_exact_mut(L)) {
+            let mut packets = packets.iter_mut();
+            let mut lanes = CipherLanes::<L>::with_nonces(
+                core::array::from_fn(|l| &keys[l][..]),
+                core::array::from_fn(|_| &b"nonce"[..]),
+            );
+            lanes.apply_keystream(core::array::from_fn(|_| &mut packets.next().unwrap()[..]));
+        }
+        black_box(&packets);
+    });
+    SESSIONS as f64 / time.as_secs_f64()
+}
+
+/// Keystream MB/s over all lanes.
+fn bulk<const L: usize>() -> f64 {
+    let keys = keys();
+    let mut lanes = CipherLanes::<L>::new(core::array::from_fn(|l| &keys[l][..]));
+    let mut bufs = vec![vec![0u8; BULK]; L];
+    let time = best(|| {
+        let mut bufs = bufs.iter_mut();
+        lanes.apply_keystream(core::array::from_fn(|_| &mut bufs.next().unwrap()[..]));
+    });
+    (L * BULK) as f64 / time.as_secs_f64() / 1e6
+}
+
+/// Hashes per second of 64 byte messages.
+fn hashes<const L: usize>() -> f64 {
+    let msgs: Vec<[u8; 64]> = (0..SESSIONS).map(|n| [n as u8; 64]).collect();
+    let mut digests = vec![[0u8; 32]; SESSIONS];
+    let time = best(|| {
+        for (msgs, digests) in msgs.chunks_exact(L).zip(digests.chunks_exact_mut(L)) {
+            let mut digests = digests.iter_mut();
+            lanes::hash::<L>(core::array::from_fn(|_| &mut digests.next().unwrap()[..]), core::array::from_fn(|l| &msgs[l][..])).unwrap();
+        }
+        black_box(&digests);
+    });
+    SESSIONS as f64 / time.as_secs_f64()
+}
+
+fn main() {
+    let keys = keys();
+    let mut packets = vec![[0u8; PACKET]; SESSIONS];
+    let scalar_sessions = SESSIONS as f64
+        / best(|| {
+            for (key, packet) in keys.iter().zip(packets.iter_mut()) {
+                SpritzCipher::with_nonce(key, b"nonce").apply_keystream(packet);
+            }
+            black_box(&packets);
+        })
+        .as_secs_f64();
+
+    let mut cipher = SpritzCipher::new(&keys[0]);
+    let mut buf = vec![0u8; BULK];
+    let scalar_bulk = BULK as f64 / best(|| cipher.apply_keystream(&mut buf)).as_secs_f64() / 1e6;
+
+    let msgs: Vec<[u8; 64]> = (0..SESSIONS).map(|n| [n as u8; 64]).collect();
+    let mut digests = vec![[0u8; 32]; SESSIONS];
+    let scalar_hashes = SESSIONS as f64
+        / best(|| {
+            for (msg, digest) in msgs.iter().zip(digests.iter_mut()) {
+                SpritzHasher::hash(digest, msg).unwrap();
+            }
+            black_box(&digests);
+        })
+        .as_secs_f64();
+
+    println!("{:>6} {:>14} {:>14} {:>14}", "lanes", "sessions/s", "keystream MB/s", "hashes/s");
+    println!("{:>6} {:>14.0} {:>14.1} {:>14.0}", "scalar", scalar_sessions, scalar_bulk, scalar_hashes);
+    let rows: [(usize, f64, f64, f64); 4] = [
+        (2, sessions::<2>(), bulk::<2>(), hashes::<2>()),
+        (4, sessions::<4>(), bulk::<4>(), hashes::<4>()),
+        (8, sessions::<8>(), bulk::<8>(), hashes::<8>()),
+        (16, sessions::<16>(), bulk::<16>(), hashes::<16>()),
+    ];
+    for (lanes, sessions, bulk, hashes) in rows {
+        println!("{:>6} {:>14.0} {:>14.1} {:>14.0}", lanes, sessions, bulk, hashes);
+    }
+}
@@ -0,0 +1,46 @@
+//! Tree hashing against the plain sequential hash of the same input.
+//!
+//! ```text
+//! cargo bench --bench tree --features parallel
+//! ```
+//!
+//! Without `parallel` only the single threaded rows are printed. The tree hash gains on one
+//! core from hashing four leaves at a time through the lanes, `hash_parallel` scales that
+//! with the number of cores.
+
+use spritz_cipher::tree::{self, TreeParams};
+use spritz_cipher::SpritzHasher;
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LEN: usize = 1 << 24;
+
+/// MB/s of `run` over `LEN` bytes, the best of a few runs.
+fn throughput(data: &[u8], mut run: impl FnMut(&[u8], &mut [u8; 32])) -> f64 {
+    let mut digest = [0u8; 32];
+    let mut best = Duration::MAX;
+    for _ in 0..3 {
+        let start = Instant::now();
+        run(data, &mut digest);
+        black_box(&digest);
+        best = best.min(start.elapsed());
+    }
+    data.len() as f64 / best.as_secs_f64() / 1e6
+}
+
+fn main() {
+    let data: Vec<u8> = (0..LEN).map(|n| (n * 31 + n / 977) as u8).collect();
+    let params = TreeParams::DEFAULT;
+
+    println!("{:>16} {:>10}", "", "MB/s");
+    let plain = throughput(&data, |data, digest| SpritzHasher::hash(digest, data).unwrap());
+    println!("{:>16} {:>10.1}", "SpritzHasher", plain);
+    let sequential = throughput(&data, |data, digest| tree::hash(params, digest, data).unwrap());
+    println!("{:>16} {:>10.1}", "tree::hash", sequential);
+    #[cfg(feature = "parallel")]
+    {
+        let parallel = throughput(&data, |data, digest| tree::hash_parallel(params, digest, data).unwrap());
+        println!("{:>16} {:>10.1}  ({} threads)", "hash_parallel", parallel, rayon::current_num_threads());
+    }
+}
@@ -4,45 +4,46 @@
 fn main() {
 
     
-        use spritz_cipher::SpritzCipherContext;
+        use spritz_cipher::{SpritzCipher, SpritzMac};
         use rand::prelude::*;
 
         let mut check = Vec::new();
-        check.extend_from_slice(&mut [0f64;256]);
+        check.extend_from_slice(&[0f64;256]);
 
         loop {
             const BUFFER_SIZE: usize = 512;
             const KEY_SIZE: usize = 512;
+            const DIGEST_SIZE: usize = 255;
     
             /* Data to input */
-            let mut msg: [u8;BUFFER_SIZE] = ['A' as u8;BUFFER_SIZE];
+            let mut msg: [u8;BUFFER_SIZE] = [b'A';BUFFER_SIZE];
             let mut key = [0u8;KEY_SIZE];
             thread_rng().fill(&mut key);
             thread_rng().fill(&mut msg);
 
-            let mut buf = [0 as u8;BUFFER_SIZE]; /* Output buffer */
+            let mut buf = [0_u8;BUFFER_SIZE]; /* Output buffer */
 
 
-            let mut context = SpritzCipherContext::setup(&key);
-            context.crypt(&msg, &mut buf);
+            let mut cipher = SpritzCipher::new(&key);
+            cipher.crypt(&msg, &mut buf).unwrap();
 
 
-            let mut context = SpritzCipherContext::setup(&key);
-            let buf2 = buf.clone();
-            context.crypt(&buf2, &mut buf);
+            let mut cipher = SpritzCipher::new(&key);
+            let buf2 = buf;
+            cipher.crypt(&buf2, &mut buf).unwrap();
 
             for val in buf2.iter(){
                 check[*val as usize] += 1.0;
             }
 
             /* Check the output */
-            assert_eq!(SpritzCipherContext::compare(&buf, &msg).unwrap(), 0);
+            assert!(spritz_cipher::ct::ct_eq(&buf, &msg));
 
             buf[0] =  buf[0].wrapping_add(1);
-            assert_ne!(SpritzCipherContext::compare(&buf, &msg).unwrap(),0);
+            assert!(!spritz_cipher::ct::ct_eq(&buf, &msg));
 
-            let mut digest = [0u8;BUFFER_SIZE]; /* Output buffer */
-            SpritzCipherContext::mac(&mut digest, &mut msg, &mut key);
+            let mut digest = [0u8;DIGEST_SIZE]; /* Output buffer */
+            SpritzMac::mac(&mut digest, &msg, &key).unwrap();
 
             for val in digest.iter(){
                 check[*val as usize] += 1.0;
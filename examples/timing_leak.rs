@@ -0,0 +1,263 @@
+//! Timing leakage harness in the style of dudect.
+//!
+//! For each target the input is either fixed (class 0) or random (class 1), chosen at random
+//! for every measurement. Welch's t-test then compares the execution times of the two classes.
+//! If the code is constant time the t statistic stays small, dudect considers `|t| > 4.5` a
+//! probable leak and `|t| > 10` a definite one.
+//!
+//! ```text
+//! cargo run --release --example timing_leak -- [--samples N] [--threshold T] [TARGET]...
+//! ```
+//!
+//! The targets are `ct_eq`, `ct_eq_length_hiding`, `compare`, `crush`, `mac`, `setup` and
+//! `mac_verify`, all of them by default.
+//! Exits with 1 if any target goes over the threshold. Timing on a busy machine is noisy, run it
+//! a few times before believing a failure.
+//!
+//! Without the `hardened` feature the S-box lookups use key dependent addresses, so `setup` and
+//! `crush` (which run whole shuffles) are expected to show a leak. Add `--features hardened` to
+//! measure the full-table-scan version.
+
+#[allow(deprecated)]
+use spritz_cipher::SpritzCipherContext;
+use spritz_cipher::{SpritzCipher, SpritzMac, SpritzRng};
+use spritz_cipher::ct;
+use spritz_cipher::stats::WelchTTest;
+
+use rand::prelude::*;
+use std::env;
+use std::process;
+use std::time::Instant;
+
+const DEFAULT_SAMPLES: usize = 100_000;
+const DEFAULT_THRESHOLD: f64 = 10.0;
+/* Measurements above this percentile are dropped from the cropped test */
+const CROP_PERCENTILE: f64 = 0.9;
+const KEY_SIZE: usize = 32;
+const MSG_SIZE: usize = 64;
+const DIGEST_SIZE: usize = 32;
+
+type Target = fn(usize) -> Report;
+
+const TARGETS: &[(&str, Target)] = &[
+    ("ct_eq", ct_eq),
+    ("ct_eq_length_hiding", ct_eq_length_hiding),
+    ("compare", compare),
+    ("crush", crush),
+    ("mac", mac),
+    ("setup", setup),
+    ("mac_verify", mac_verify),
+];
+
+struct Report {
+    raw: WelchTTest,
+    cropped: WelchTTest,
+}
+
+impl Report {
+    fn max_t(&self) -> f64 {
+        self.raw.t().abs().max(self.cropped.t().abs())
+    }
+}
+
+/// Time `run` over `samples` inputs made by `prepare(class)`, with the classes interleaved at random.
+///
+/// All inputs are prepared before measuring so preparing them doesn't disturb the caches or the
+/// branch predictor differently for the two classes.
+fn measure<I, P, R>(samples: usize, mut prepare: P, mut run: R) -> Report
+where
+    P: FnMut(usize) -> I,
+    R: FnMut(&mut I),
+{
+    let mut rng = thread_rng();
+    let mut inputs: Vec<(usize, I)> = (0..samples)
+        .map(|_| {
+            let class = rng.gen_range(0, 2);
+            (class, prepare(class))
+        })
+        .collect();
+
+    let mut times = Vec::with_capacity(samples);
+    for (class, input) in inputs.iter_mut() {
+        let start = Instant::now();
+        run(input);
+        let elapsed = start.elapsed().as_nanos() as f64;
+
+        times.push((*class, elapsed));
+    }
+
+    let mut sorted: Vec<f64> = times.iter().map(|&(_, t)| t).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let cutoff = sorted[((sorted.len() as f64 * CROP_PERCENTILE) as usize).min(sorted.len() - 1)];
+
+    let mut report = Report { raw: WelchTTest::new(), cropped: WelchTTest::new() };
+    for &(class, time) in times.iter() {
+        report.raw.push(class, time);
+        if time <= cutoff {
+            report.cropped.push(class, time);
+        }
+    }
+    report
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    thread_rng().fill(&mut data[..]);
+    data
+}
+
+/// Equal buffers against differing buffers.
+fn ct_eq(samples: usize) -> Report {
+    let secret = random_bytes(DIGEST_SIZE);
+    measure(
+        samples,
+        |class| if class == 0 { secret.clone() } else { random_bytes(DIGEST_SIZE) },
+        |other| {
+            black_box(ct::ct_eq(&secret, other));
+        },
+    )
+}
+
+/// Equal buffers against differing buffers, with the secret length hidden.
+fn ct_eq_length_hiding(samples: usize) -> Report {
+    let secret = random_bytes(DIGEST_SIZE);
+    measure(
+        samples,
+        |class| if class == 0 { secret.clone() } else { random_bytes(DIGEST_SIZE) },
+        |other| {
+            black_box(ct::ct_eq_length_hiding(&secret, other));
+        },
+    )
+}
+
+/// The deprecated `compare`, equal buffers against differing buffers.
+#[allow(deprecated)]
+fn compare(samples: usize) -> Report {
+    let secret = random_bytes(DIGEST_SIZE);
+    measure(
+        samples,
+        |class| if class == 0 { secret.clone() } else { random_bytes(DIGEST_SIZE) },
+        |other| {
+            black_box(SpritzCipherContext::compare(&secret, other).unwrap());
+        },
+    )
+}
+
+/// One shuffle (three whips and two crushes) on a state from a fixed or a random key.
+fn crush(samples: usize) -> Report {
+    let key = random_bytes(KEY_SIZE);
+    measure(
+        samples,
+        |class| {
+            if class == 0 {
+                SpritzRng::new(&key)
+            } else {
+                SpritzRng::new(&random_bytes(KEY_SIZE))
+            }
+        },
+        |rng| {
+            /* N / 4 bytes fill the absorb buffer, the next drip shuffles */
+            rng.add_entropy(&[0u8; 64]);
+            black_box(rng.random8());
+        },
+    )
+}
+
+/// MAC of a fixed message under a fixed or a random key.
+fn mac(samples: usize) -> Report {
+    let key = random_bytes(KEY_SIZE);
+    let msg = random_bytes(MSG_SIZE);
+    measure(
+        samples,
+        |class| if class == 0 { key.clone() } else { random_bytes(KEY_SIZE) },
+        |key| {
+            let mut digest = [0u8; DIGEST_SIZE];
+            SpritzMac::mac(&mut digest, &msg, key).unwrap();
+            black_box(digest);
+        },
+    )
+}
+
+/// Key setup with a fixed or a random key.
+fn setup(samples: usize) -> Report {
+    let key = random_bytes(KEY_SIZE);
+    measure(
+        samples,
+        |class| if class == 0 { key.clone() } else { random_bytes(KEY_SIZE) },
+        |key| {
+            black_box(SpritzCipher::new(key));
+        },
+    )
+}
+
+/// MAC verification with the correct tag against a random tag.
+fn mac_verify(samples: usize) -> Report {
+    let key = random_bytes(KEY_SIZE);
+    let msg = random_bytes(MSG_SIZE);
+    let mut tag = [0u8; DIGEST_SIZE];
+    SpritzMac::mac(&mut tag, &msg, &key).unwrap();
+
+    measure(
+        samples,
+        |class| if class == 0 { tag.to_vec() } else { random_bytes(DIGEST_SIZE) },
+        |received| {
+            let mut mac = SpritzMac::new(&key);
+            mac.update(&msg);
+            black_box(mac.verify(received));
+        },
+    )
+}
+
+fn black_box<T>(value: T) -> T {
+    std::hint::black_box(value)
+}
+
+fn main() {
+    let mut samples = DEFAULT_SAMPLES;
+    let mut threshold = DEFAULT_THRESHOLD;
+    let mut selected = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--samples" => samples = args.next().and_then(|v| v.parse().ok()).expect("--samples needs a number"),
+            "--threshold" => threshold = args.next().and_then(|v| v.parse().ok()).expect("--threshold needs a number"),
+            name if TARGETS.iter().any(|(n, _)| *n == name) => selected.push(name.to_string()),
+            _ => {
+                eprintln!("unknown argument '{}'", arg);
+                process::exit(2);
+            }
+        }
+    }
+    /* The t-test needs at least two measurements */
+    if samples < 2 {
+        eprintln!("--samples must be at least 2");
+        process::exit(2);
+    }
+
+    let mut leaks = 0;
+    for (name, target) in TARGETS.iter() {
+        if !selected.is_empty() && !selected.iter().any(|s| s == name) {
+            continue;
+        }
+
+        let report = target(samples);
+        let [n0, n1] = report.raw.counts();
+        let [m0, m1] = report.raw.means();
+        let verdict = if report.max_t() > threshold {
+            leaks += 1;
+            "LEAK"
+        } else {
+            "ok"
+        };
+        println!(
+            "{:<20} n = {:>7}/{:<7} mean = {:>10.1}/{:<10.1} ns  t = {:>7.2}  cropped t = {:>7.2}  {}",
+            name, n0, n1, m0, m1, report.raw.t(), report.cropped.t(), verdict
+        );
+    }
+
+    if leaks > 0 {
+        println!("{} target(s) over the threshold |t| > {}", leaks, threshold);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,191 @@
+//! `spritz-rng` - write a raw Spritz keystream to standard output.
+//!
+//! The output is meant to be piped into external randomness test suites, e.g.
+//!
+//! ```text
+//! spritz-rng --seed 1234 | RNG_test stdin8
+//! spritz-rng --seed 1234 --words | dieharder -a -g 200
+//! ```
+//!
+//! The stream is unbounded unless `--count` is given.
+
+use spritz_cipher::encoding;
+use spritz_cipher::SpritzRng;
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const USAGE: &str = "\
+Usage: spritz-rng [OPTION]...
+Write the Spritz keystream for a key (and nonce) to standard output.
+
+  -s, --seed TEXT       use the bytes of TEXT as the key
+  -k, --key HEX         use the hex encoded key
+      --key-file FILE   use the contents of FILE as the key
+  -n, --nonce HEX       set up the context with a hex encoded nonce (IV)
+  -d, --drop N          discard the first N bytes of keystream
+  -w, --words           write random32() values (little endian) instead of bytes
+  -c, --count N         stop after N bytes of output (default: unbounded)
+  -r, --rate N          limit the output to N bytes per second
+
+  -h, --help            display this help and exit
+";
+
+#[derive(Default)]
+struct Options {
+    key: Option<Vec<u8>>,
+    nonce: Option<Vec<u8>>,
+    drop: u64,
+    words: bool,
+    count: Option<u64>,
+    rate: Option<u64>,
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("spritz-rng: {}", msg);
+    eprintln!("Try 'spritz-rng --help' for more information.");
+    process::exit(1);
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options::default();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next().unwrap_or_else(|| fail(&format!("option '{}' requires an argument", name)))
+        };
+        let number = |name: &str, value: String| {
+            value.parse::<u64>().unwrap_or_else(|_| fail(&format!("invalid value for '{}': '{}'", name, value)))
+        };
+        let hex = |what: &str, value: String| {
+            let mut bytes = vec![0u8; value.len() / 2];
+            encoding::decode_hex(&value, &mut bytes).unwrap_or_else(|_| fail(&format!("the {} must be hex encoded", what)));
+            bytes
+        };
+
+        match arg.as_str() {
+            "-s" | "--seed" => opts.key = Some(value(&arg).into_bytes()),
+            "-k" | "--key" => opts.key = Some(hex("key", value(&arg))),
+            "--key-file" => {
+                let file = value(&arg);
+                match fs::read(&file) {
+                    Ok(key) => opts.key = Some(key),
+                    Err(e) => fail(&format!("{}: {}", file, e)),
+                }
+            }
+            "-n" | "--nonce" => opts.nonce = Some(hex("nonce", value(&arg))),
+            "-d" | "--drop" => opts.drop = number(&arg, value(&arg)),
+            "-w" | "--words" => opts.words = true,
+            "-c" | "--count" => opts.count = Some(number(&arg, value(&arg))),
+            "-r" | "--rate" => {
+                let rate = number(&arg, value(&arg));
+                if rate == 0 {
+                    fail("the rate must be greater than 0");
+                }
+                opts.rate = Some(rate);
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => fail(&format!("unrecognized option '{}'", arg)),
+        }
+    }
+
+    if opts.key.is_none() {
+        fail("a key is required (--seed, --key or --key-file)");
+    }
+
+    opts
+}
+
+fn fill(rng: &mut SpritzRng, words: bool, buf: &mut [u8]) {
+    if words {
+        for chunk in buf.chunks_mut(4) {
+            let word = rng.random32().to_le_bytes();
+            chunk.copy_from_slice(&word[..chunk.len()]);
+        }
+    } else {
+        rng.fill_bytes(buf);
+    }
+}
+
+fn run(opts: &Options) -> io::Result<()> {
+    let key = opts.key.as_deref().unwrap_or(&[]);
+    let mut rng = match opts.nonce {
+        Some(ref nonce) => SpritzRng::with_nonce(key, nonce),
+        None => SpritzRng::new(key),
+    };
+
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    /* Discard a buffer at a time, the keystream doesn't depend on how it is split */
+    let mut dropped = opts.drop;
+    while dropped > 0 {
+        let len = dropped.min(CHUNK_SIZE as u64) as usize;
+        rng.fill_bytes(&mut buf[..len]);
+        dropped -= len as u64;
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut remaining = opts.count;
+    let mut written: u64 = 0;
+    let start = Instant::now();
+
+    loop {
+        let limit = match remaining {
+            Some(0) => break,
+            Some(n) => n.min(CHUNK_SIZE as u64) as usize,
+            None => CHUNK_SIZE,
+        };
+        /* Keep each write under one second worth of output so the rate limit stays smooth */
+        let mut len = match opts.rate {
+            Some(rate) => limit.min(rate as usize).max(1),
+            None => limit,
+        };
+        /* Whole words, so the next buffer carries on the random32() stream, only --count cuts the last */
+        if opts.words {
+            len = (len - len % 4).max(4).min(limit);
+        }
+
+        fill(&mut rng, opts.words, &mut buf[..len]);
+        out.write_all(&buf[..len])?;
+
+        written += len as u64;
+        if let Some(ref mut n) = remaining {
+            *n -= len as u64;
+        }
+
+        if let Some(rate) = opts.rate {
+            let due = Duration::from_secs_f64(written as f64 / rate as f64);
+            let elapsed = start.elapsed();
+            if due > elapsed {
+                out.flush()?;
+                thread::sleep(due - elapsed);
+            }
+        }
+    }
+
+    out.flush()
+}
+
+fn main() {
+    let opts = parse_args();
+
+    match run(&opts) {
+        Ok(()) => {}
+        /* The reading end (e.g. a test suite) has seen enough */
+        Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+        Err(e) => {
+            eprintln!("spritz-rng: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! `spritz-wycheproof` - emit Wycheproof-style JSON test vectors.
+//!
+//! The vectors are shared with the other language implementations. Every test
+//! has a `result` of `valid` or `invalid`, the invalid ones are edge cases that
+//! an implementation must reject (tampered tags, wrong digest lengths, ...).
+//!
+//! There are groups for the hash, the MAC and the stream cipher. New modes (e.g. an AEAD)
+//! get their own `*_groups` function and group type.
+//!
+//! The inputs come from a Spritz keystream with a fixed key, so the output is
+//! deterministic. A copy lives in `tests/vectors/spritz_wycheproof.json`,
+//! regenerate it with
+//!
+//! ```text
+//! cargo run --bin spritz-wycheproof > tests/vectors/spritz_wycheproof.json
+//! ```
+
+use spritz_cipher::encoding::Hex;
+use spritz_cipher::{SpritzCipher, SpritzHasher, SpritzMac, SpritzRng};
+
+use std::fmt::Write;
+
+const GENERATOR_VERSION: &str = env!("CARGO_PKG_VERSION");
+const SEED: &[u8] = b"spritz wycheproof";
+
+const NOTES: &[(&str, &str)] = &[
+    ("EmptyKey", "The key is empty."),
+    ("EmptyMessage", "The message is empty."),
+    ("LongKey", "The key is longer than N / 4 bytes, so absorbing it shuffles the state part way through."),
+    ("LongMessage", "The message is longer than N / 4 bytes, so absorbing it shuffles the state part way through."),
+    ("MaxDigestSize", "The digest has the largest size that fits the one byte length encoding (255 bytes)."),
+    ("ModifiedCiphertext", "The ciphertext was modified, it must not match the encryption of the message."),
+    ("ModifiedDigest", "A bit of the digest was flipped."),
+    ("ModifiedTag", "A bit of the tag was flipped."),
+    ("Pseudorandom", "The inputs were generated pseudorandomly."),
+    ("TruncatedDigest", "The digest is a prefix of the correct digest. Spritz absorbs the digest length, so a shorter digest is not a prefix of a longer one."),
+    ("TruncatedTag", "The tag is a prefix of the correct tag. Spritz absorbs the tag length, so a shorter tag is not a prefix of a longer one."),
+    ("WrongKey", "The tag was computed with a different key."),
+];
+
+/// Deterministic input generator.
+struct Inputs {
+    rng: SpritzRng,
+}
+
+impl Inputs {
+    fn new() -> Inputs {
+        Inputs { rng: SpritzRng::new(SEED) }
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        self.rng.fill_bytes(&mut out);
+        out
+    }
+}
+
+struct Test {
+    comment: String,
+    fields: Vec<(&'static str, Vec<u8>)>,
+    valid: bool,
+    flags: Vec<&'static str>,
+}
+
+impl Test {
+    fn new(comment: &str, valid: bool, flags: &[&'static str]) -> Test {
+        Test { comment: comment.into(), fields: Vec::new(), valid, flags: flags.to_vec() }
+    }
+
+    fn field(mut self, name: &'static str, value: &[u8]) -> Test {
+        self.fields.push((name, value.to_vec()));
+        self
+    }
+}
+
+struct Group {
+    kind: &'static str,
+    sizes: Vec<(&'static str, usize)>,
+    tests: Vec<Test>,
+}
+
+fn hash(msg: &[u8], len: usize) -> Vec<u8> {
+    let mut digest = vec![0u8; len];
+    SpritzHasher::hash(&mut digest, msg).unwrap();
+    digest
+}
+
+fn mac(key: &[u8], msg: &[u8], len: usize) -> Vec<u8> {
+    let mut tag = vec![0u8; len];
+    SpritzMac::mac(&mut tag, msg, key).unwrap();
+    tag
+}
+
+fn encrypt(key: &[u8], iv: &[u8], msg: &[u8]) -> Vec<u8> {
+    let mut ct = vec![0u8; msg.len()];
+    SpritzCipher::with_nonce(key, iv).crypt(msg, &mut ct).unwrap();
+    ct
+}
+
+fn flip_bit(data: &[u8], bit: usize) -> Vec<u8> {
+    let mut data = data.to_vec();
+    data[bit / 8] ^= 1 << (bit % 8);
+    data
+}
+
+fn hash_groups(inputs: &mut Inputs) -> Vec<Group> {
+    let mut groups = Vec::new();
+
+    for &size in [1usize, 16, 20, 32, 64, 255].iter() {
+        let mut tests = Vec::new();
+
+        let digest = hash(&[], size);
+        tests.push(Test::new("empty message", true, &["EmptyMessage"]).field("msg", &[]).field("digest", &digest));
+
+        for &len in [1usize, 7, 32, 64, 65, 200, 1000].iter() {
+            let msg = inputs.bytes(len);
+            let mut flags = vec!["Pseudorandom"];
+            if len > 64 {
+                flags.push("LongMessage");
+            }
+            if size == 255 {
+                flags.push("MaxDigestSize");
+            }
+            tests.push(Test::new(&format!("{} byte message", len), true, &flags).field("msg", &msg).field("digest", &hash(&msg, size)));
+        }
+
+        let msg = inputs.bytes(32);
+        let digest = hash(&msg, size);
+        for &bit in [0, size * 4 + 1, size * 8 - 1].iter() {
+            tests.push(
+                Test::new(&format!("flipped bit {} of the digest", bit), false, &["ModifiedDigest"])
+                    .field("msg", &msg)
+                    .field("digest", &flip_bit(&digest, bit)),
+            );
+        }
+        if size < 255 {
+            /* Prefix of a longer digest doesn't match */
+            let longer = hash(&msg, size + 1);
+            tests.push(
+                Test::new("prefix of a longer digest", false, &["TruncatedDigest"])
+                    .field("msg", &msg)
+                    .field("digest", &longer[..size]),
+            );
+        }
+        if size > 1 {
+            tests.push(
+                Test::new("truncated digest", false, &["TruncatedDigest"])
+                    .field("msg", &msg)
+                    .field("digest", &digest[..size - 1]),
+            );
+        }
+
+        groups.push(Group { kind: "SpritzHashTest", sizes: vec![("digestSize", size * 8)], tests });
+    }
+
+    groups
+}
+
+fn mac_groups(inputs: &mut Inputs) -> Vec<Group> {
+    let mut groups = Vec::new();
+
+    for &(key_size, tag_size) in [(0usize, 32usize), (16, 16), (32, 32), (100, 32), (32, 255)].iter() {
+        let mut tests = Vec::new();
+        let mut key_flags = Vec::new();
+        if key_size == 0 {
+            key_flags.push("EmptyKey");
+        }
+        if key_size > 64 {
+            key_flags.push("LongKey");
+        }
+
+        let key = inputs.bytes(key_size);
+        let mut flags = key_flags.clone();
+        flags.push("EmptyMessage");
+        tests.push(Test::new("empty message", true, &flags).field("key", &key).field("msg", &[]).field("tag", &mac(&key, &[], tag_size)));
+
+        for &len in [1usize, 16, 65, 500].iter() {
+            let key = inputs.bytes(key_size);
+            let msg = inputs.bytes(len);
+            let mut flags = key_flags.clone();
+            flags.push("Pseudorandom");
+            if len > 64 {
+                flags.push("LongMessage");
+            }
+            tests.push(
+                Test::new(&format!("{} byte message", len), true, &flags)
+                    .field("key", &key)
+                    .field("msg", &msg)
+                    .field("tag", &mac(&key, &msg, tag_size)),
+            );
+        }
+
+        let key = inputs.bytes(key_size);
+        let msg = inputs.bytes(32);
+        let tag = mac(&key, &msg, tag_size);
+        for &bit in [0, 1, tag_size * 8 - 1].iter() {
+            tests.push(
+                Test::new(&format!("flipped bit {} of the tag", bit), false, &["ModifiedTag"])
+                    .field("key", &key)
+                    .field("msg", &msg)
+                    .field("tag", &flip_bit(&tag, bit)),
+            );
+        }
+        tests.push(
+            Test::new("truncated tag", false, &["TruncatedTag"])
+                .field("key", &key)
+                .field("msg", &msg)
+                .field("tag", &tag[..tag_size - 1]),
+        );
+        let other_key = inputs.bytes(key_size.max(1));
+        tests.push(
+            Test::new("tag computed with a different key", false, &["WrongKey"])
+                .field("key", &key)
+                .field("msg", &msg)
+                .field("tag", &mac(&other_key, &msg, tag_size)),
+        );
+
+        groups.push(Group { kind: "SpritzMacTest", sizes: vec![("keySize", key_size * 8), ("tagSize", tag_size * 8)], tests });
+    }
+
+    groups
+}
+
+fn stream_groups(inputs: &mut Inputs) -> Vec<Group> {
+    let mut groups = Vec::new();
+
+    for &(key_size, iv_size) in [(16usize, 0usize), (32, 8), (32, 16), (100, 24)].iter() {
+        let mut tests = Vec::new();
+        let mut key_flags = Vec::new();
+        if key_size > 64 {
+            key_flags.push("LongKey");
+        }
+
+        let key = inputs.bytes(key_size);
+        let iv = inputs.bytes(iv_size);
+        let mut flags = key_flags.clone();
+        flags.push("EmptyMessage");
+        tests.push(Test::new("empty message", true, &flags).field("key", &key).field("iv", &iv).field("msg", &[]).field("ct", &[]));
+
+        for &len in [1usize, 16, 256, 1000].iter() {
+            let key = inputs.bytes(key_size);
+            let iv = inputs.bytes(iv_size);
+            let msg = inputs.bytes(len);
+            let mut flags = key_flags.clone();
+            flags.push("Pseudorandom");
+            tests.push(
+                Test::new(&format!("{} byte message", len), true, &flags)
+                    .field("key", &key)
+                    .field("iv", &iv)
+                    .field("msg", &msg)
+                    .field("ct", &encrypt(&key, &iv, &msg)),
+            );
+        }
+
+        let key = inputs.bytes(key_size);
+        let iv = inputs.bytes(iv_size);
+        let msg = inputs.bytes(32);
+        let ct = encrypt(&key, &iv, &msg);
+        tests.push(
+            Test::new("modified ciphertext", false, &["ModifiedCiphertext"])
+                .field("key", &key)
+                .field("iv", &iv)
+                .field("msg", &msg)
+                .field("ct", &flip_bit(&ct, 100)),
+        );
+
+        groups.push(Group { kind: "SpritzStreamTest", sizes: vec![("keySize", key_size * 8), ("ivSize", iv_size * 8)], tests });
+    }
+
+    groups
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn render(groups: &[Group]) -> String {
+    let count: usize = groups.iter().map(|g| g.tests.len()).sum();
+    let mut out = String::new();
+
+    out.push_str("{\n");
+    writeln!(out, "  \"algorithm\" : \"SPRITZ\",").unwrap();
+    writeln!(out, "  \"generatorVersion\" : {},", json_string(GENERATOR_VERSION)).unwrap();
+    writeln!(out, "  \"numberOfTests\" : {},", count).unwrap();
+    writeln!(out, "  \"header\" : [").unwrap();
+    writeln!(out, "    \"Test vectors for the Spritz hash, MAC and stream cipher (setup_with_IV + crypt).\",").unwrap();
+    writeln!(out, "    \"Sizes are in bits, all byte strings are hex encoded.\"").unwrap();
+    writeln!(out, "  ],").unwrap();
+
+    writeln!(out, "  \"notes\" : {{").unwrap();
+    for (n, (flag, note)) in NOTES.iter().enumerate() {
+        let sep = if n + 1 < NOTES.len() { "," } else { "" };
+        writeln!(out, "    {} : {}{}", json_string(flag), json_string(note), sep).unwrap();
+    }
+    writeln!(out, "  }},").unwrap();
+
+    writeln!(out, "  \"testGroups\" : [").unwrap();
+    let mut tc_id = 0;
+    for (g, group) in groups.iter().enumerate() {
+        writeln!(out, "    {{").unwrap();
+        writeln!(out, "      \"type\" : {},", json_string(group.kind)).unwrap();
+        for (name, size) in group.sizes.iter() {
+            writeln!(out, "      {} : {},", json_string(name), size).unwrap();
+        }
+        writeln!(out, "      \"tests\" : [").unwrap();
+        for (t, test) in group.tests.iter().enumerate() {
+            tc_id += 1;
+            writeln!(out, "        {{").unwrap();
+            writeln!(out, "          \"tcId\" : {},", tc_id).unwrap();
+            writeln!(out, "          \"comment\" : {},", json_string(&test.comment)).unwrap();
+            for (name, value) in test.fields.iter() {
+                writeln!(out, "          {} : \"{}\",", json_string(name), Hex(value)).unwrap();
+            }
+            writeln!(out, "          \"result\" : \"{}\",", if test.valid { "valid" } else { "invalid" }).unwrap();
+            let flags: Vec<String> = test.flags.iter().map(|f| json_string(f)).collect();
+            writeln!(out, "          \"flags\" : [{}]", flags.join(", ")).unwrap();
+            writeln!(out, "        }}{}", if t + 1 < group.tests.len() { "," } else { "" }).unwrap();
+        }
+        writeln!(out, "      ]").unwrap();
+        writeln!(out, "    }}{}", if g + 1 < groups.len() { "," } else { "" }).unwrap();
+    }
+    writeln!(out, "  ]").unwrap();
+    out.push_str("}\n");
+
+    out
+}
+
+fn main() {
+    let mut inputs = Inputs::new();
+
+    let mut groups = hash_groups(&mut inputs);
+    groups.extend(mac_groups(&mut inputs));
+    groups.extend(stream_groups(&mut inputs));
+
+    print!("{}", render(&groups));
+}
@@ -0,0 +1,390 @@
+//! `spritzsum` - compute and check Spritz message digests.
+//!
+//! Works like `sha256sum` and friends. The default output is the coreutils
+//! format `<hex>  <file>`, `--tag` switches to the BSD format
+//! `SPRITZ-256 (<file>) = <hex>` and `-c` reads either format back and
+//! checks the listed files.
+//!
+//! When a key is given the digest is a Spritz MAC instead of a plain hash.
+
+use spritz_cipher::{SpritzHasher, SpritzMac};
+use spritz_cipher::ct;
+use spritz_cipher::encoding::{self, Hex};
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const DEFAULT_DIGEST_SIZE: usize = 32;
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+const USAGE: &str = "\
+Usage: spritzsum [OPTION]... [FILE]...
+Print or check Spritz digests. With no FILE, or when FILE is -, read standard input.
+
+  -l, --length BYTES    digest length in bytes (default 32)
+  -c, --check           read digests from the FILEs and check them
+      --tag             create a BSD-style checksum
+  -k, --key HEX         compute a keyed digest (MAC) with the hex encoded key
+      --key-file FILE   compute a keyed digest (MAC) with the key read from FILE
+  -r, --recursive       hash the files inside directories, recursively
+
+The following options are only useful when checking:
+      --quiet           don't print OK for each successfully verified file
+      --status          don't output anything, the exit code shows success
+      --strict          exit non-zero for improperly formatted checksum lines
+
+  -h, --help            display this help and exit
+";
+
+#[derive(Default)]
+struct Options {
+    length: usize,
+    check: bool,
+    tag: bool,
+    key: Option<Vec<u8>>,
+    recursive: bool,
+    quiet: bool,
+    status: bool,
+    strict: bool,
+    files: Vec<String>,
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("spritzsum: {}", msg);
+    eprintln!("Try 'spritzsum --help' for more information.");
+    process::exit(1);
+}
+
+fn parse_args() -> Options {
+    let mut opts = Options { length: DEFAULT_DIGEST_SIZE, ..Default::default() };
+    let mut args = env::args().skip(1);
+    let mut only_files = false;
+
+    while let Some(arg) = args.next() {
+        if only_files || arg == "-" || !arg.starts_with('-') {
+            opts.files.push(arg);
+            continue;
+        }
+
+        match arg.as_str() {
+            "--" => only_files = true,
+            "-l" | "--length" => {
+                let value = args.next().unwrap_or_else(|| fail("option requires an argument -- 'l'"));
+                opts.length = match value.parse() {
+                    Ok(len) if len > 0 && len <= u8::MAX as usize => len,
+                    _ => fail(&format!("invalid digest length: '{}'", value)),
+                };
+            }
+            "-c" | "--check" => opts.check = true,
+            "--tag" => opts.tag = true,
+            "-k" | "--key" => {
+                let value = args.next().unwrap_or_else(|| fail("option requires an argument -- 'k'"));
+                let mut key = vec![0u8; value.len() / 2];
+                encoding::decode_hex(&value, &mut key).unwrap_or_else(|_| fail("the key must be hex encoded"));
+                opts.key = Some(key);
+            }
+            "--key-file" => {
+                let value = args.next().unwrap_or_else(|| fail("option '--key-file' requires an argument"));
+                match fs::read(&value) {
+                    Ok(key) => opts.key = Some(key),
+                    Err(e) => fail(&format!("{}: {}", value, e)),
+                }
+            }
+            "-r" | "--recursive" => opts.recursive = true,
+            "--quiet" => opts.quiet = true,
+            "--status" => opts.status = true,
+            "--strict" => opts.strict = true,
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => fail(&format!("unrecognized option '{}'", arg)),
+        }
+    }
+
+    if opts.check && opts.tag {
+        fail("the --tag option is meaningless when verifying checksums");
+    }
+    if opts.check && opts.recursive {
+        fail("the --recursive option is meaningless when verifying checksums");
+    }
+
+    if opts.files.is_empty() {
+        opts.files.push("-".into());
+    }
+
+    opts
+}
+
+/// Name used in BSD-style tagged lines, e.g. `SPRITZ-256` or `SPRITZ-MAC-256`.
+fn tag_name(keyed: bool, length: usize) -> String {
+    if keyed {
+        format!("SPRITZ-MAC-{}", length * 8)
+    } else {
+        format!("SPRITZ-{}", length * 8)
+    }
+}
+
+/// A plain hash, or a MAC when a key is given.
+enum Summer {
+    Hash(SpritzHasher),
+    Mac(SpritzMac),
+}
+
+impl Summer {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Summer::Hash(hasher) => hasher.update(data),
+            Summer::Mac(mac) => mac.update(data),
+        }
+    }
+
+    fn finalize(self, digest: &mut [u8]) {
+        let result = match self {
+            Summer::Hash(hasher) => hasher.finalize(digest),
+            Summer::Mac(mac) => mac.finalize(digest),
+        };
+        result.expect("the digest length is checked when parsing");
+    }
+}
+
+/// Hash everything from `reader` into a `length` bytes long digest.
+fn digest_reader<R: Read>(mut reader: R, length: usize, key: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    let mut summer = match key {
+        Some(key) => Summer::Mac(SpritzMac::new(key)),
+        None => Summer::Hash(SpritzHasher::new()),
+    };
+
+    let mut buf = vec![0u8; READ_BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        summer.update(&buf[..n]);
+    }
+
+    let mut digest = vec![0u8; length];
+    summer.finalize(&mut digest);
+    Ok(digest)
+}
+
+fn digest_file(name: &str, length: usize, key: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    if name == "-" {
+        let stdin = io::stdin();
+        let lock = stdin.lock();
+        digest_reader(lock, length, key)
+    } else {
+        digest_reader(File::open(name)?, length, key)
+    }
+}
+
+/// Expand directories into the regular files below them, in a stable order.
+fn walk(path: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for entry in entries {
+        walk(&entry, out)?;
+    }
+    Ok(())
+}
+
+fn print_digest(opts: &Options, name: &str, digest: &[u8]) {
+    if opts.tag {
+        println!("{} ({}) = {}", tag_name(opts.key.is_some(), digest.len()), name, Hex(digest));
+    } else {
+        println!("{}  {}", Hex(digest), name);
+    }
+}
+
+fn generate(opts: &Options) -> bool {
+    let mut ok = true;
+
+    for file in &opts.files {
+        let mut names = Vec::new();
+        if file != "-" && Path::new(file).is_dir() {
+            if !opts.recursive {
+                eprintln!("spritzsum: {}: Is a directory", file);
+                ok = false;
+                continue;
+            }
+            if let Err(e) = walk(Path::new(file), &mut names) {
+                eprintln!("spritzsum: {}: {}", file, e);
+                ok = false;
+                continue;
+            }
+        } else {
+            names.push(PathBuf::from(file));
+        }
+
+        for name in names {
+            let name = name.to_string_lossy();
+            match digest_file(&name, opts.length, opts.key.as_deref()) {
+                Ok(digest) => print_digest(opts, &name, &digest),
+                Err(e) => {
+                    eprintln!("spritzsum: {}: {}", name, e);
+                    ok = false;
+                }
+            }
+        }
+    }
+
+    ok
+}
+
+/// A single parsed line of a checksum file.
+struct CheckLine<'a> {
+    digest: Vec<u8>,
+    file: &'a str,
+}
+
+/// Parse either `<hex>  <file>`, `<hex> *<file>` or `SPRITZ[-MAC]-<bits> (<file>) = <hex>`.
+fn parse_check_line(line: &str, keyed: bool) -> Option<CheckLine<'_>> {
+    let decode = |hex: &str| {
+        let mut digest = vec![0u8; hex.len() / 2];
+        encoding::decode_hex(hex, &mut digest).ok().map(|_| digest)
+    };
+
+    let (digest, file) = if let Some(rest) = line.strip_prefix("SPRITZ-") {
+        let (name, rest) = rest.split_at(rest.find(" (")?);
+        let (file, hex) = rest[2..].rsplit_once(") = ")?;
+
+        let bits = match name.strip_prefix("MAC-") {
+            Some(bits) if keyed => bits,
+            None if !keyed => name,
+            _ => return None,
+        };
+        let digest = decode(hex)?;
+        if bits.parse::<usize>().ok()? != digest.len() * 8 {
+            return None;
+        }
+        (digest, file)
+    } else {
+        let (hex, rest) = line.split_at(line.find(' ')?);
+        let file = rest.strip_prefix("  ").or_else(|| rest.strip_prefix(" *"))?;
+        (decode(hex)?, file)
+    };
+
+    /* Both formats can only hold digests `Summer` can produce */
+    if digest.is_empty() || digest.len() > u8::MAX as usize || file.is_empty() {
+        return None;
+    }
+    Some(CheckLine { digest, file })
+}
+
+fn check_list<R: BufRead>(opts: &Options, list_name: &str, reader: R) -> io::Result<bool> {
+    let mut bad_format = 0;
+    let mut bad_read = 0;
+    let mut mismatched = 0;
+    let mut matched = 0;
+
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end_matches('\r');
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let entry = match parse_check_line(line, opts.key.is_some()) {
+            Some(entry) => entry,
+            None => {
+                bad_format += 1;
+                if !opts.status {
+                    eprintln!("spritzsum: {}: {}: improperly formatted Spritz checksum line", list_name, line_no + 1);
+                }
+                continue;
+            }
+        };
+
+        let digest = match digest_file(entry.file, entry.digest.len(), opts.key.as_deref()) {
+            Ok(digest) => digest,
+            Err(e) => {
+                bad_read += 1;
+                if !opts.status {
+                    eprintln!("spritzsum: {}: {}", entry.file, e);
+                    println!("{}: FAILED open or read", entry.file);
+                }
+                continue;
+            }
+        };
+
+        if ct::ct_eq(&digest, &entry.digest) {
+            matched += 1;
+            if !opts.status && !opts.quiet {
+                println!("{}: OK", entry.file);
+            }
+        } else {
+            mismatched += 1;
+            if !opts.status {
+                println!("{}: FAILED", entry.file);
+            }
+        }
+    }
+
+    if !opts.status {
+        if matched + mismatched + bad_read == 0 {
+            eprintln!("spritzsum: {}: no properly formatted Spritz checksum lines found", list_name);
+        }
+        if bad_format > 0 {
+            eprintln!("spritzsum: WARNING: {} line(s) improperly formatted", bad_format);
+        }
+        if bad_read > 0 {
+            eprintln!("spritzsum: WARNING: {} listed file(s) could not be read", bad_read);
+        }
+        if mismatched > 0 {
+            eprintln!("spritzsum: WARNING: {} computed checksum(s) did NOT match", mismatched);
+        }
+    }
+
+    Ok(matched > 0
+        && mismatched == 0
+        && bad_read == 0
+        && (!opts.strict || bad_format == 0))
+}
+
+fn check(opts: &Options) -> bool {
+    let mut ok = true;
+
+    for file in &opts.files {
+        let result = if file == "-" {
+            let stdin = io::stdin();
+            let lock = stdin.lock();
+            check_list(opts, "standard input", lock)
+        } else {
+            File::open(file).and_then(|f| check_list(opts, file, BufReader::new(f)))
+        };
+
+        match result {
+            Ok(true) => {}
+            Ok(false) => ok = false,
+            Err(e) => {
+                eprintln!("spritzsum: {}: {}", file, e);
+                ok = false;
+            }
+        }
+    }
+
+    ok
+}
+
+fn main() {
+    let opts = parse_args();
+
+    let ok = if opts.check { check(&opts) } else { generate(&opts) };
+    io::stdout().flush().ok();
+
+    process::exit(if ok { 0 } else { 1 });
+}
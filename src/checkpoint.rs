@@ -0,0 +1,220 @@
+//! Checkpoints for resuming a classic Spritz stream part way through.
+//!
+//! The plain keystream can only be reached by generating everything before it. A
+//! [`Checkpointer`] encrypts or decrypts a stream exactly like [`SpritzCipher`], and every
+//! `interval` bytes hands out a [`Checkpoint`], a sealed copy of the cipher state at that
+//! position. Kept next to the stream, the checkpoints let [`seek`] continue from the nearest one
+//! before a position, with the same output as decrypting from the start. Data that was already
+//! encrypted with `crypt` needs one pass through a `Checkpointer` to build its index.
+//!
+//! A state gives away the keystream on both sides of it, so checkpoints are encrypted and
+//! authenticated under a key derived from the stream's key and nonce. They can be stored
+//! with the ciphertext, and one from another stream or a changed one is rejected.
+//!
+//! ```
+//! use spritz_cipher::checkpoint::{self, Checkpointer};
+//!
+//! let mut file = vec![0u8; 10_000];
+//! let mut index = Vec::new();
+//! let mut cipher = Checkpointer::new(b"key", Some(b"nonce"), 4096).unwrap();
+//! cipher.apply_keystream(&mut file, |checkpoint| index.push(checkpoint));
+//! assert_eq!(index.len(), 2);
+//!
+//! /* Decrypt from byte 9000, starting at the checkpoint at 8192 */
+//! let mut tail = file[9000..].to_vec();
+//! checkpoint::seek(b"key", Some(b"nonce"), &index, 9000).unwrap().apply_keystream(&mut tail);
+//! assert!(tail.iter().all(|&b| b == 0));
+//! ```
+
+use crate::sponge::{Sponge, STATE_LEN};
+use crate::{SpritzCipher, SpritzCipherError, SpritzKeySchedule, SpritzMac, Tag};
+
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use zeroize::Zeroizing;
+
+const POSITION_LEN: usize = 8;
+const TAG_LEN: usize = 32;
+
+/// A sealed cipher state and the stream position it belongs to.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    bytes: [u8; Checkpoint::LEN],
+}
+
+impl Checkpoint {
+    /// The length of a stored checkpoint, the position, the encrypted state and a tag.
+    pub const LEN: usize = POSITION_LEN + STATE_LEN + TAG_LEN;
+
+    /// Read a stored checkpoint, it is only checked when it is used.
+    ///
+    /// Returns an error if `bytes` isn't [`LEN`](Self::LEN) bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Checkpoint, SpritzCipherError> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| SpritzCipherError::InvalidLength { expected: Checkpoint::LEN, actual: bytes.len() })?;
+        Ok(Checkpoint { bytes })
+    }
+
+    /// The bytes to store.
+    pub fn as_bytes(&self) -> &[u8; Checkpoint::LEN] {
+        &self.bytes
+    }
+
+    /// The stream position the state is at, not yet authenticated.
+    pub fn position(&self) -> u64 {
+        let mut position = [0u8; POSITION_LEN];
+        position.copy_from_slice(&self.bytes[..POSITION_LEN]);
+        u64::from_le_bytes(position)
+    }
+}
+
+impl fmt::Debug for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Checkpoint").field("position", &self.position()).finish_non_exhaustive()
+    }
+}
+
+/// The key checkpoints of the stream keyed with `key` and `nonce` are sealed under.
+fn sealing_key(key: &[u8], nonce: Option<&[u8]>) -> SpritzKeySchedule {
+    let mut mac = SpritzMac::new(key);
+    mac.update(b"spritz checkpoint");
+    match nonce {
+        Some(nonce) => {
+            mac.update(&[1]);
+            mac.update(nonce);
+        }
+        None => mac.update(&[0]),
+    }
+    let derived: Tag<32> = mac.finalize_tag();
+    SpritzKeySchedule::new(derived.as_bytes())
+}
+
+/// The cipher for the checkpoint at `position`, and the MAC key taken from its first bytes.
+fn checkpoint_cipher(sealing: &SpritzKeySchedule, position: &[u8]) -> (SpritzCipher, Zeroizing<[u8; TAG_LEN]>) {
+    let mut cipher = sealing.cipher(position);
+    let mut mac_key = Zeroizing::new([0u8; TAG_LEN]);
+    cipher.apply_keystream(&mut *mac_key);
+    (cipher, mac_key)
+}
+
+/// Encrypt-then-MAC `stream`, which is at `position`.
+fn seal(sealing: &SpritzKeySchedule, position: u64, stream: &Sponge) -> Checkpoint {
+    let mut bytes = [0u8; Checkpoint::LEN];
+    let (header, rest) = bytes.split_at_mut(POSITION_LEN);
+    let (state, tag) = rest.split_at_mut(STATE_LEN);
+    header.copy_from_slice(&position.to_le_bytes());
+
+    let mut saved = Zeroizing::new([0u8; STATE_LEN]);
+    stream.save(&mut saved);
+    state.copy_from_slice(&*saved);
+    let (mut cipher, mac_key) = checkpoint_cipher(sealing, header);
+    cipher.apply_keystream(state);
+
+    let mut mac = SpritzMac::new(&*mac_key);
+    mac.update(header);
+    mac.update(state);
+    /* A 32 byte tag never exceeds the maximum digest size */
+    let _ = mac.finalize(tag);
+    Checkpoint { bytes }
+}
+
+/// Check and decrypt a checkpoint into the cipher state it holds.
+fn open(sealing: &SpritzKeySchedule, checkpoint: &Checkpoint) -> Result<Sponge, SpritzCipherError> {
+    let (header, rest) = checkpoint.bytes.split_at(POSITION_LEN);
+    let (state, tag) = rest.split_at(STATE_LEN);
+    let (mut cipher, mac_key) = checkpoint_cipher(sealing, header);
+
+    let mut mac = SpritzMac::new(&*mac_key);
+    mac.update(header);
+    mac.update(state);
+    if !mac.verify(tag) {
+        return Err(SpritzCipherError::AuthenticationFailed);
+    }
+
+    let mut saved = Zeroizing::new([0u8; STATE_LEN]);
+    saved.copy_from_slice(state);
+    cipher.apply_keystream(&mut *saved);
+    let mut stream = Sponge::init();
+    stream.restore(&saved);
+    Ok(stream)
+}
+
+/// A Spritz stream cipher that records a [`Checkpoint`] every `interval` bytes.
+pub struct Checkpointer {
+    stream: Sponge,
+    sealing: SpritzKeySchedule,
+    interval: usize,
+    position: u64,
+}
+
+impl Checkpointer {
+    /// The same keystream as [`SpritzCipher::new`], or [`SpritzCipher::with_nonce`] if there is
+    /// a nonce.
+    ///
+    /// Returns an error if `interval` is 0.
+    pub fn new(key: &[u8], nonce: Option<&[u8]>, interval: usize) -> Result<Checkpointer, SpritzCipherError> {
+        if interval < 1 {
+            return Err(SpritzCipherError::TooSmall { min: 1, actual: interval });
+        }
+        Ok(Checkpointer {
+            stream: Sponge::keyed(key, nonce),
+            sealing: sealing_key(key, nonce),
+            interval,
+            position: 0,
+        })
+    }
+
+    /// How many bytes of keystream have been used.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Encrypt or decrypt `data` in place, passing `record` a checkpoint each time the position
+    /// reaches a multiple of the interval.
+    pub fn apply_keystream(&mut self, data: &mut [u8], mut record: impl FnMut(Checkpoint)) {
+        let mut data = data;
+        while !data.is_empty() {
+            let to_next = self.interval - (self.position % self.interval as u64) as usize;
+            let (now, rest) = data.split_at_mut(to_next.min(data.len()));
+            self.stream.apply_keystream(now);
+            self.position += now.len() as u64;
+            data = rest;
+
+            if self.position.is_multiple_of(self.interval as u64) {
+                record(seal(&self.sealing, self.position, &self.stream));
+            }
+        }
+    }
+}
+
+/// The cipher of the stream keyed with `key` and `nonce`, continuing from `checkpoint`.
+///
+/// Returns an error if the checkpoint was changed or belongs to another stream.
+pub fn resume(key: &[u8], nonce: Option<&[u8]>, checkpoint: &Checkpoint) -> Result<SpritzCipher, SpritzCipherError> {
+    open(&sealing_key(key, nonce), checkpoint).map(SpritzCipher::from_sponge)
+}
+
+/// The cipher of the stream keyed with `key` and `nonce`, at byte `position`.
+///
+/// It resumes from the checkpoint in `index` closest before `position`, or starts from the
+/// beginning if there is none, then skips to `position`. Returns an error if that checkpoint
+/// doesn't authenticate.
+pub fn seek(key: &[u8], nonce: Option<&[u8]>, index: &[Checkpoint], position: u64) -> Result<SpritzCipher, SpritzCipherError> {
+    let nearest = index
+        .iter()
+        .filter(|checkpoint| checkpoint.position() <= position)
+        .max_by_key(|checkpoint| checkpoint.position());
+    let (mut stream, start) = match nearest {
+        Some(checkpoint) => (open(&sealing_key(key, nonce), checkpoint)?, checkpoint.position()),
+        None => (Sponge::keyed(key, nonce), 0),
+    };
+
+    let mut skip = position - start;
+    while skip > 0 {
+        let step = usize::try_from(skip).unwrap_or(usize::MAX);
+        stream.skip_keystream(step);
+        skip -= step as u64;
+    }
+    Ok(SpritzCipher::from_sponge(stream))
+}
@@ -0,0 +1,161 @@
+//! Encryption and decryption with the Spritz keystream.
+
+use crate::mem::InPlace;
+use crate::sponge::Sponge;
+use crate::{Nonce, SpritzCipherError, SpritzCtr, SpritzKey};
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+use core::mem::MaybeUninit;
+
+/// The Spritz stream cipher, created from a key and optionally a nonce.
+///
+/// Encrypting and decrypting are the same operation, XOR with the keystream.
+/// ```
+/// use spritz_cipher::SpritzCipher;
+///
+/// let msg = *b"attack at dawn";
+/// let mut buf = msg;
+///
+/// SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut buf);
+/// assert_ne!(buf, msg);
+///
+/// SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut buf);
+/// assert_eq!(buf, msg);
+/// ```
+pub struct SpritzCipher {
+    sponge: Sponge,
+}
+
+impl SpritzCipher {
+    /// Setup the cipher with a key.
+    /// * Parameter key:    The key.
+    pub fn new(key: &[u8]) -> SpritzCipher {
+        SpritzCipher { sponge: Sponge::keyed(key, None) }
+    }
+
+    /// Setup the cipher with a key and nonce/salt/iv.
+    /// * Parameter key:      The key.
+    /// * Parameter nonce:    The nonce (salt), never reuse one with the same key.
+    pub fn with_nonce(key: &[u8], nonce: &[u8]) -> SpritzCipher {
+        SpritzCipher { sponge: Sponge::keyed(key, Some(nonce)) }
+    }
+
+    /// Setup the cipher with a key in `slot`, without the state ever being moved.
+    pub fn new_in<'a>(slot: &'a mut MaybeUninit<SpritzCipher>, key: &[u8]) -> InPlace<'a, SpritzCipher> {
+        let mut cipher = InPlace::new(slot, SpritzCipher { sponge: Sponge::init() });
+        cipher.sponge.key(key, None);
+        cipher
+    }
+
+    /// Setup the cipher with a key and nonce in `slot`, without the state ever being moved.
+    pub fn with_nonce_in<'a>(slot: &'a mut MaybeUninit<SpritzCipher>, key: &[u8], nonce: &[u8]) -> InPlace<'a, SpritzCipher> {
+        let mut cipher = InPlace::new(slot, SpritzCipher { sponge: Sponge::init() });
+        cipher.sponge.key(key, Some(nonce));
+        cipher
+    }
+
+    /// Setup the cipher with a key on the heap, without the state ever being moved.
+    #[cfg(feature = "alloc")]
+    pub fn new_boxed(key: &[u8]) -> Box<SpritzCipher> {
+        let mut cipher = Box::new(SpritzCipher { sponge: Sponge::init() });
+        cipher.sponge.key(key, None);
+        cipher
+    }
+
+    /// Setup the cipher with a key and nonce on the heap, without the state ever being moved.
+    #[cfg(feature = "alloc")]
+    pub fn with_nonce_boxed(key: &[u8], nonce: &[u8]) -> Box<SpritzCipher> {
+        let mut cipher = Box::new(SpritzCipher { sponge: Sponge::init() });
+        cipher.sponge.key(key, Some(nonce));
+        cipher
+    }
+
+    /// Setup the cipher with a typed key.
+    pub fn from_key<const K: usize>(key: &SpritzKey<K>) -> SpritzCipher {
+        SpritzCipher::new(key.as_bytes())
+    }
+
+    /// Setup the cipher with a typed key and nonce.
+    pub fn from_key_and_nonce<const K: usize, const M: usize>(key: &SpritzKey<K>, nonce: &Nonce<M>) -> SpritzCipher {
+        SpritzCipher::with_nonce(key.as_bytes(), nonce.as_bytes())
+    }
+
+    /// A cipher continuing from a state that has been keyed.
+    pub(crate) fn from_sponge(sponge: Sponge) -> SpritzCipher {
+        SpritzCipher { sponge }
+    }
+
+    /// Encrypt or decrypt `data` into `data_out` by XOR-ing it with the keystream.
+    ///
+    /// Returns an error if the array lengths don't match.
+    pub fn crypt(&mut self, data: &[u8], data_out: &mut [u8]) -> Result<(), SpritzCipherError> {
+        self.sponge.crypt(data, data_out)
+    }
+
+    /// Encrypt or decrypt `data` in place.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        self.sponge.apply_keystream(data);
+    }
+}
+
+/// A key absorbed once, for encrypting many messages with different nonces.
+///
+/// [`SpritzCipher::with_nonce`] absorbs the whole key for every message. The schedule keeps the
+/// state after the key and the stop before the nonce, so each [`cipher`](Self::cipher) only
+/// copies it and absorbs the nonce. The shuffle after the nonce still runs per message, since
+/// the keystream has to depend on the nonce. Keys of 64 bytes or more need a shuffle of their
+/// own while being absorbed, so for them this halves the cost of keying, for shorter keys it
+/// saves little. `cargo bench --bench key_schedule` measures it.
+/// ```
+/// use spritz_cipher::{SpritzCipher, SpritzKeySchedule};
+///
+/// let schedule = SpritzKeySchedule::new(b"a long term key shared with the device");
+///
+/// let mut packet = *b"temperature=21.5";
+/// schedule.cipher(&7u64.to_le_bytes()).apply_keystream(&mut packet);
+///
+/// let mut expected = *b"temperature=21.5";
+/// SpritzCipher::with_nonce(b"a long term key shared with the device", &7u64.to_le_bytes())
+///     .apply_keystream(&mut expected);
+/// assert_eq!(packet, expected);
+/// ```
+pub struct SpritzKeySchedule {
+    sponge: Sponge,
+}
+
+impl SpritzKeySchedule {
+    /// Absorb `key`, and the stop that separates it from a nonce.
+    pub fn new(key: &[u8]) -> SpritzKeySchedule {
+        /* A MAC starts the same way */
+        SpritzKeySchedule { sponge: Sponge::mac_keyed(key) }
+    }
+
+    /// Absorb a typed key.
+    pub fn from_key<const K: usize>(key: &SpritzKey<K>) -> SpritzKeySchedule {
+        SpritzKeySchedule::new(key.as_bytes())
+    }
+
+    /// The same cipher as [`SpritzCipher::with_nonce`] with this key.
+    pub fn cipher(&self, nonce: &[u8]) -> SpritzCipher {
+        let mut cipher = SpritzCipher { sponge: Sponge::init() };
+        cipher.sponge.copy_from(&self.sponge);
+        cipher.sponge.key_nonce(nonce);
+        cipher
+    }
+
+    /// [`cipher`](Self::cipher) in `slot`, without the state ever being moved.
+    pub fn cipher_in<'a>(&self, slot: &'a mut MaybeUninit<SpritzCipher>, nonce: &[u8]) -> InPlace<'a, SpritzCipher> {
+        let mut cipher = InPlace::new(slot, SpritzCipher { sponge: Sponge::init() });
+        cipher.sponge.copy_from(&self.sponge);
+        cipher.sponge.key_nonce(nonce);
+        cipher
+    }
+
+    /// The same keystream as [`SpritzCtr::new`] with this key.
+    pub fn ctr(&self, nonce: &[u8]) -> SpritzCtr {
+        let mut base = Sponge::init();
+        base.copy_from(&self.sponge);
+        SpritzCtr::from_keyed(base, nonce)
+    }
+}
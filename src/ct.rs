@@ -0,0 +1,227 @@
+//! Constant-time comparison and selection.
+//!
+//! A small subset of the [`subtle`](https://docs.rs/subtle) API, without the dependency.
+//! With the `subtle` feature [`Choice`] converts to and from `subtle::Choice`.
+//!
+//! ```
+//! use spritz_cipher::SpritzHasher;
+//! use spritz_cipher::ct::{self, ConstantTimeEq};
+//!
+//! let mut digest = [0u8; 32];
+//! SpritzHasher::hash(&mut digest, b"arcfour").unwrap();
+//!
+//! let expected = digest;
+//! assert!(ct::ct_eq(&digest, &expected));
+//! assert!(bool::from(digest[..].ct_eq(&expected[..])));
+//!
+//! digest[0] ^= 1;
+//! assert!(!ct::ct_eq(&digest, &expected));
+//! ```
+
+use crate::SpritzCipherError;
+
+use core::hint::black_box;
+use core::ops::{BitAnd, BitOr, Not};
+
+/// The result of a constant-time comparison, `1` for true and `0` for false.
+///
+/// Unlike a `bool` the compiler doesn't know the value can only be 0 or 1, so it is less
+/// likely to branch on it. Convert it to a `bool` at the last moment.
+#[derive(Copy, Clone, Debug)]
+pub struct Choice(u8);
+
+impl Choice {
+    /// The value as `0` or `1`.
+    pub fn unwrap_u8(self) -> u8 {
+        self.0
+    }
+
+    /// All ones for true, zero for false.
+    fn mask(self) -> u8 {
+        self.0.wrapping_neg()
+    }
+}
+
+impl From<u8> for Choice {
+    /// Only the lowest bit of `value` is used.
+    fn from(value: u8) -> Choice {
+        Choice(black_box(value & 1))
+    }
+}
+
+impl From<Choice> for bool {
+    fn from(choice: Choice) -> bool {
+        black_box(choice.0) != 0
+    }
+}
+
+impl BitAnd for Choice {
+    type Output = Choice;
+    fn bitand(self, rhs: Choice) -> Choice {
+        Choice(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for Choice {
+    type Output = Choice;
+    fn bitor(self, rhs: Choice) -> Choice {
+        Choice(self.0 | rhs.0)
+    }
+}
+
+impl Not for Choice {
+    type Output = Choice;
+    fn not(self) -> Choice {
+        Choice(self.0 ^ 1)
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl From<Choice> for subtle::Choice {
+    fn from(choice: Choice) -> subtle::Choice {
+        subtle::Choice::from(choice.0)
+    }
+}
+
+#[cfg(feature = "subtle")]
+impl From<subtle::Choice> for Choice {
+    fn from(choice: subtle::Choice) -> Choice {
+        Choice::from(choice.unwrap_u8())
+    }
+}
+
+/// Equality that takes the same time whatever the values are.
+pub trait ConstantTimeEq {
+    /// `Choice(1)` if `self == other`.
+    fn ct_eq(&self, other: &Self) -> Choice;
+
+    /// `Choice(1)` if `self != other`.
+    fn ct_ne(&self, other: &Self) -> Choice {
+        !self.ct_eq(other)
+    }
+}
+
+/// Select between two values without branching.
+pub trait ConditionallySelectable: Copy {
+    /// `a` if `choice` is 0, `b` if `choice` is 1.
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self;
+
+    /// Set `self` to `other` if `choice` is 1.
+    fn conditional_assign(&mut self, other: &Self, choice: Choice) {
+        *self = Self::conditional_select(self, other, choice);
+    }
+
+    /// Swap `a` and `b` if `choice` is 1.
+    fn conditional_swap(a: &mut Self, b: &mut Self, choice: Choice) {
+        let t = *a;
+        a.conditional_assign(b, choice);
+        b.conditional_assign(&t, choice);
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty),*) => {$(
+        impl ConstantTimeEq for $t {
+            fn ct_eq(&self, other: &$t) -> Choice {
+                let x = self ^ other;
+                /* x | -x has the top bit set unless x == 0 */
+                let nonzero = (x | x.wrapping_neg()) >> (<$t>::BITS - 1);
+                Choice::from((nonzero as u8) ^ 1)
+            }
+        }
+
+        impl ConditionallySelectable for $t {
+            fn conditional_select(a: &$t, b: &$t, choice: Choice) -> $t {
+                let mask = (choice.0 as $t).wrapping_neg();
+                a ^ (mask & (a ^ b))
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u8, u16, u32, u64, usize);
+
+impl<T: ConstantTimeEq> ConstantTimeEq for [T] {
+    /// The lengths are not secret, slices of different lengths are never equal.
+    fn ct_eq(&self, other: &[T]) -> Choice {
+        if self.len() != other.len() {
+            return Choice(0);
+        }
+
+        let mut equal = Choice(1);
+        for (a, b) in self.iter().zip(other.iter()) {
+            equal = equal & a.ct_eq(b);
+        }
+        equal
+    }
+}
+
+impl<T: ConstantTimeEq, const N: usize> ConstantTimeEq for [T; N] {
+    fn ct_eq(&self, other: &[T; N]) -> Choice {
+        self[..].ct_eq(&other[..])
+    }
+}
+
+/// Compare two byte strings in constant time.
+///
+/// Only the lengths are allowed to leak, buffers of different lengths are not equal.
+#[inline(never)]
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Compare a secret against an input without leaking the length of the secret.
+///
+/// The work done only depends on the length of `input`, so the time taken doesn't tell
+/// how long `secret` is or how much of it matched.
+#[inline(never)]
+pub fn ct_eq_length_hiding(secret: &[u8], input: &[u8]) -> bool {
+    if secret.is_empty() {
+        return input.is_empty();
+    }
+
+    let mut differ = (secret.len() as u64).ct_ne(&(input.len() as u64)).mask();
+    let last = secret.len() - 1;
+    for (i, byte) in input.iter().enumerate() {
+        /* Past the end of the secret keep reading its last byte, the lengths differ anyway */
+        let index = usize::conditional_select(&last, &i, ct_lt(i, secret.len()));
+        differ |= secret[index] ^ byte;
+    }
+    bool::from(differ.ct_eq(&0))
+}
+
+/// `Choice(1)` if `a < b`.
+fn ct_lt(a: usize, b: usize) -> Choice {
+    let (a, b) = (a as u64, b as u64);
+    /* The borrow out of a - b, computed without comparing */
+    let borrow = ((!a & b) | (!(a ^ b) & a.wrapping_sub(b))) >> 63;
+    Choice::from(borrow as u8)
+}
+
+/// Copy `src` into `dst` if `choice` is 1, otherwise leave `dst` as it is.
+///
+/// Returns an error if the lengths don't match.
+pub fn conditional_copy(dst: &mut [u8], src: &[u8], choice: Choice) -> Result<(), SpritzCipherError> {
+    if dst.len() != src.len() {
+        return Err(SpritzCipherError::LengthsDontMatch { expected: dst.len(), actual: src.len() });
+    }
+
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        d.conditional_assign(s, choice);
+    }
+    Ok(())
+}
+
+/// Write `a` into `out` if `choice` is 0, `b` if it is 1.
+///
+/// Returns an error if the lengths don't match.
+pub fn conditional_select(out: &mut [u8], a: &[u8], b: &[u8], choice: Choice) -> Result<(), SpritzCipherError> {
+    if let Some(other) = [a.len(), b.len()].iter().find(|&&len| len != out.len()) {
+        return Err(SpritzCipherError::LengthsDontMatch { expected: out.len(), actual: *other });
+    }
+
+    for ((o, a), b) in out.iter_mut().zip(a.iter()).zip(b.iter()) {
+        *o = u8::conditional_select(a, b, choice);
+    }
+    Ok(())
+}
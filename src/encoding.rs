@@ -0,0 +1,172 @@
+//! Hex and base64 for keys, nonces, tags and digests.
+//!
+//! Both directions work without branching on or indexing by the data, so parsing a key
+//! doesn't leak it through timing. Base64 uses the standard alphabet with `=` padding.
+//!
+//! ```
+//! use spritz_cipher::encoding::{self, Base64, Hex};
+//!
+//! assert_eq!(Hex(b"ABC").to_string(), "414243");
+//! assert_eq!(Base64(b"ABCD").to_string(), "QUJDRA==");
+//!
+//! let mut key = [0u8; 3];
+//! encoding::decode_hex("414243", &mut key).unwrap();
+//! assert_eq!(&key, b"ABC");
+//! ```
+
+use crate::SpritzCipherError;
+
+use core::fmt::{self, Write};
+
+/// Formats bytes as lower case hex.
+#[derive(Clone, Copy)]
+pub struct Hex<'a>(pub &'a [u8]);
+
+/// Formats bytes as padded base64.
+#[derive(Clone, Copy)]
+pub struct Base64<'a>(pub &'a [u8]);
+
+/// -1 if `lo <= c <= hi`, otherwise 0.
+fn range_mask(c: i16, lo: i16, hi: i16) -> i16 {
+    /* Both differences are negative only inside the range, the shift spreads the sign */
+    ((lo - 1 - c) & (c - hi - 1)) >> 8
+}
+
+/// The hex digit for a nibble.
+fn hex_char(nibble: u8) -> char {
+    let n = nibble as i16;
+    /* '0' + n, plus the gap between '9' + 1 and 'a' for n > 9 */
+    (n + 48 + (((9 - n) >> 8) & 39)) as u8 as char
+}
+
+/// The value of a hex digit in either case.
+fn hex_value(c: u8) -> Option<u8> {
+    let c = c as i16;
+    let lower = c | 0x20;
+    let digit = range_mask(c, 48, 57);
+    let alpha = range_mask(lower, 97, 102);
+    let value = (digit & (c - 48)) | (alpha & (lower - 87));
+    if (digit | alpha) == 0 {
+        None
+    } else {
+        Some(value as u8)
+    }
+}
+
+/// The base64 character for a 6 bit value.
+fn base64_char(value: u8) -> char {
+    let v = value as i16;
+    /* 'A'..'Z', then shift into 'a'..'z', '0'..'9', '+' and '/' */
+    let mut diff = 65;
+    diff += ((25 - v) >> 8) & 6;
+    diff -= ((51 - v) >> 8) & 75;
+    diff -= ((61 - v) >> 8) & 15;
+    diff += ((62 - v) >> 8) & 3;
+    (v + diff) as u8 as char
+}
+
+/// The value of a base64 character.
+fn base64_value(c: u8) -> Option<u8> {
+    let c = c as i16;
+    let upper = range_mask(c, 65, 90);
+    let lower = range_mask(c, 97, 122);
+    let digit = range_mask(c, 48, 57);
+    let plus = range_mask(c, 43, 43);
+    let slash = range_mask(c, 47, 47);
+    let value = (upper & (c - 65)) | (lower & (c - 71)) | (digit & (c + 4)) | (plus & 62) | (slash & 63);
+    if (upper | lower | digit | plus | slash) == 0 {
+        None
+    } else {
+        Some(value as u8)
+    }
+}
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0.iter() {
+            f.write_char(hex_char(byte >> 4))?;
+            f.write_char(hex_char(byte & 0x0f))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Base64<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.0.chunks(3) {
+            let mut block = [0u8; 3];
+            block[..chunk.len()].copy_from_slice(chunk);
+            let bits = (block[0] as u32) << 16 | (block[1] as u32) << 8 | block[2] as u32;
+
+            for k in 0..4 {
+                if k <= chunk.len() {
+                    f.write_char(base64_char((bits >> (18 - 6 * k)) as u8 & 0x3f))?;
+                } else {
+                    f.write_char('=')?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decode exactly `out.len()` bytes of hex, in either case, into `out`.
+///
+/// Odd length input or a bad digit is [`SpritzCipherError::InvalidEncoding`] at the offending
+/// digit, input of the wrong length is [`SpritzCipherError::InvalidLength`]. For input of unknown
+/// length size `out` as `hex.len() / 2`.
+pub fn decode_hex(hex: &str, out: &mut [u8]) -> Result<(), SpritzCipherError> {
+    let hex = hex.as_bytes();
+    /* The last digit has no partner */
+    if !hex.len().is_multiple_of(2) {
+        return Err(SpritzCipherError::InvalidEncoding { position: hex.len() - 1 });
+    }
+    if hex.len() != 2 * out.len() {
+        return Err(SpritzCipherError::InvalidLength { expected: out.len(), actual: hex.len() / 2 });
+    }
+
+    for (k, byte) in out.iter_mut().enumerate() {
+        let high = hex_value(hex[2 * k]).ok_or(SpritzCipherError::InvalidEncoding { position: 2 * k })?;
+        let low = hex_value(hex[2 * k + 1]).ok_or(SpritzCipherError::InvalidEncoding { position: 2 * k + 1 })?;
+        *byte = high << 4 | low;
+    }
+    Ok(())
+}
+
+/// Decode padded base64 into `out`, it must decode to exactly `out.len()` bytes and the bits
+/// left over before the padding must be zero.
+pub(crate) fn decode_base64(base64: &str, out: &mut [u8]) -> Result<(), SpritzCipherError> {
+    let input = base64.as_bytes();
+    if !input.len().is_multiple_of(4) {
+        return Err(SpritzCipherError::InvalidEncoding { position: input.len() });
+    }
+
+    let padding = input.iter().rev().take_while(|&&c| c == b'=').count();
+    if padding > 2 {
+        return Err(SpritzCipherError::InvalidEncoding { position: input.len() - padding });
+    }
+    let decoded_len = input.len() / 4 * 3 - padding;
+    if decoded_len != out.len() {
+        return Err(SpritzCipherError::InvalidLength { expected: out.len(), actual: decoded_len });
+    }
+
+    let data = &input[..input.len() - padding];
+    let mut bits: u32 = 0;
+    let mut count = 0;
+    let mut written = 0;
+    for (position, c) in data.iter().enumerate() {
+        let value = base64_value(*c).ok_or(SpritzCipherError::InvalidEncoding { position })?;
+        bits = bits << 6 | value as u32;
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            out[written] = (bits >> count) as u8;
+            written += 1;
+        }
+    }
+    /* The bits the padding leaves over must be zero, so each value has one encoding */
+    if bits & ((1 << count) - 1) != 0 {
+        return Err(SpritzCipherError::InvalidEncoding { position: data.len() - 1 });
+    }
+    Ok(())
+}
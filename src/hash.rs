@@ -0,0 +1,170 @@
+//! The Spritz hash function.
+
+use crate::lanes::{self, BatchKeys};
+use crate::mem::InPlace;
+use crate::sponge::Sponge;
+use crate::{Digest, LengthEncoding, SpritzCipherError};
+
+use core::mem::MaybeUninit;
+#[cfg(feature = "std")]
+use std::io::IoSlice;
+
+/// An incremental Spritz hash.
+///
+/// [`finalize`](Self::finalize) consumes the hasher, so it can't be finalised twice or
+/// updated afterwards.
+/// ```
+/// use spritz_cipher::SpritzHasher;
+///
+/// let mut hasher = SpritzHasher::new();
+/// hasher.update(b"arc");
+/// hasher.update(b"four");
+///
+/// let mut digest = [0u8; 32];
+/// hasher.finalize(&mut digest).unwrap();
+///
+/// let mut expected = [0u8; 32];
+/// SpritzHasher::hash(&mut expected, b"arcfour").unwrap();
+/// assert_eq!(digest, expected);
+/// assert_eq!(&digest[..4], &[0xff, 0x8c, 0xf2, 0x68]);
+/// ```
+///
+/// Finalising twice doesn't compile,
+/// ```compile_fail
+/// use spritz_cipher::SpritzHasher;
+///
+/// let hasher = SpritzHasher::new();
+/// hasher.finalize(&mut [0u8; 32]).unwrap();
+/// hasher.finalize(&mut [0u8; 32]).unwrap();
+/// ```
+/// and neither does hashing with a cipher.
+/// ```compile_fail
+/// use spritz_cipher::SpritzCipher;
+///
+/// SpritzCipher::new(b"key").update(b"data");
+/// ```
+pub struct SpritzHasher {
+    sponge: Sponge,
+}
+
+impl Default for SpritzHasher {
+    fn default() -> SpritzHasher {
+        SpritzHasher::new()
+    }
+}
+
+impl SpritzHasher {
+    /// An empty hash.
+    pub fn new() -> SpritzHasher {
+        SpritzHasher { sponge: Sponge::init() }
+    }
+
+    /// An empty hash in `slot`, for hashing secrets without the state ever being moved.
+    pub fn new_in(slot: &mut MaybeUninit<SpritzHasher>) -> InPlace<'_, SpritzHasher> {
+        InPlace::new(slot, SpritzHasher::new())
+    }
+
+    /// Add a message/data chunk `data` to the hash.
+    pub fn update(&mut self, data: &[u8]) {
+        self.sponge.absorb_bytes(data);
+    }
+
+    /// Add scattered buffers to the hash, the same as adding each in turn.
+    #[cfg(feature = "std")]
+    pub fn update_vectored(&mut self, bufs: &[IoSlice<'_>]) {
+        for buf in bufs {
+            self.sponge.absorb_bytes(buf);
+        }
+    }
+
+    /// Output the digest, its length is `digest.len()`.
+    ///
+    /// Returns an error if the digest is longer than [`MAX_DIGEST_SIZE`](crate::MAX_DIGEST_SIZE)
+    /// bytes, use [`finalize_with`](Self::finalize_with) for longer digests.
+    pub fn finalize(self, digest: &mut [u8]) -> Result<(), SpritzCipherError> {
+        self.finalize_with(digest, LengthEncoding::Compat)
+    }
+
+    /// Output the digest, absorbing its length with `encoding`.
+    ///
+    /// Never fails with [`LengthEncoding::Extended`].
+    pub fn finalize_with(mut self, digest: &mut [u8], encoding: LengthEncoding) -> Result<(), SpritzCipherError> {
+        self.sponge.squeeze(digest, encoding)
+    }
+
+    /// Output an `N` byte digest, any `N` works (see [`LengthEncoding::Extended`]).
+    pub fn finalize_digest<const N: usize>(mut self) -> Digest<N> {
+        Digest::squeeze(|out| self.sponge.squeeze_extended(out))
+    }
+
+    /// The `N` byte digest of `data`.
+    /// ```
+    /// use spritz_cipher::{Digest, SpritzHasher};
+    ///
+    /// let digest: Digest = SpritzHasher::digest(b"arcfour");
+    /// assert_eq!(digest, Digest::from_hex("ff8cf268094c87b95f74ce6fee9d3003a5f9fe6944653cd50e66bf189c63f699").unwrap());
+    /// ```
+    pub fn digest<const N: usize>(data: &[u8]) -> Digest<N> {
+        let mut hasher = SpritzHasher::new();
+        hasher.update(data);
+        hasher.finalize_digest()
+    }
+
+    /// Hash `data` in one go.
+    pub fn hash(digest: &mut [u8], data: &[u8]) -> Result<(), SpritzCipherError> {
+        SpritzHasher::hash_with(digest, data, LengthEncoding::Compat)
+    }
+
+    /// Hash `data` in one go, absorbing the digest length with `encoding`.
+    pub fn hash_with(digest: &mut [u8], data: &[u8], encoding: LengthEncoding) -> Result<(), SpritzCipherError> {
+        let mut hasher = SpritzHasher::new();
+        hasher.update(data);
+        hasher.finalize_with(digest, encoding)
+    }
+
+    /// Hash scattered buffers as one message.
+    #[cfg(feature = "std")]
+    pub fn hash_vectored(digest: &mut [u8], bufs: &[IoSlice<'_>]) -> Result<(), SpritzCipherError> {
+        let mut hasher = SpritzHasher::new();
+        hasher.update_vectored(bufs);
+        hasher.finalize(digest)
+    }
+
+    /// Hash each of `messages` into the next `digest_len` bytes of `out`.
+    ///
+    /// The same digests as [`hash`](Self::hash) on each message, computed several at a time
+    /// with [`lanes`](crate::lanes). `out` must be exactly `messages.len() * digest_len` bytes.
+    /// ```
+    /// use spritz_cipher::SpritzHasher;
+    ///
+    /// let records: [&[u8]; 3] = [b"one", b"two", b"three"];
+    /// let mut digests = [0u8; 3 * 16];
+    /// SpritzHasher::hash_batch(&mut digests, 16, &records).unwrap();
+    ///
+    /// let mut expected = [0u8; 16];
+    /// SpritzHasher::hash(&mut expected, b"two").unwrap();
+    /// assert_eq!(digests[16..32], expected);
+    /// ```
+    pub fn hash_batch(out: &mut [u8], digest_len: usize, messages: &[&[u8]]) -> Result<(), SpritzCipherError> {
+        lanes::batch(out, digest_len, messages, BatchKeys::Unkeyed)
+    }
+
+    /// [`hash_batch`](Self::hash_batch) with each message made of scattered buffers.
+    #[cfg(feature = "std")]
+    pub fn hash_batch_vectored(out: &mut [u8], digest_len: usize, messages: &[&[IoSlice<'_>]]) -> Result<(), SpritzCipherError> {
+        lanes::batch(out, digest_len, messages, BatchKeys::Unkeyed)
+    }
+}
+
+/// The finalisers of a hash made with [`SpritzHasher::new_in`], they wipe the slot afterwards.
+impl InPlace<'_, SpritzHasher> {
+    /// [`SpritzHasher::finalize`] without moving the state out of its slot.
+    pub fn finalize(self, digest: &mut [u8]) -> Result<(), SpritzCipherError> {
+        self.finalize_with(digest, LengthEncoding::Compat)
+    }
+
+    /// [`SpritzHasher::finalize_with`] without moving the state out of its slot.
+    pub fn finalize_with(mut self, digest: &mut [u8], encoding: LengthEncoding) -> Result<(), SpritzCipherError> {
+        self.sponge.squeeze(digest, encoding)
+    }
+}
@@ -0,0 +1,200 @@
+//! Many independent Spritz states run side by side.
+//!
+//! A single Spritz state is slow because every step waits on the table lookups of the one
+//! before. The types here run `L` states in lockstep, one step of each lane in turn, so the
+//! lookups of different lanes overlap. Each lane gives exactly what the scalar types give for
+//! its own key, nonce or message, the lanes don't need equal lengths but run fastest when the
+//! lengths match. With the `hardened` feature the lanes run one after the other through the
+//! scalar code, which keeps each S-box in its own state, so they are no faster than a loop.
+//!
+//! Four to eight lanes set up short sessions about three times faster than one at a time on
+//! x86-64, more lanes don't help. The `avx2` feature runs groups of eight lanes with AVX2
+//! gathers over an interleaved table when the CPU has AVX2. Every step is a chain of dependent
+//! lookups and two data dependent writes per lane and AVX2 has no scatter, so on a Xeon with
+//! AVX2 that set up sessions at about half the speed of the portable lanes and ran bulk
+//! keystream at about three quarters, which is why it is off by default.
+//! Compare on your own CPU with
+//!
+//! ```text
+//! cargo bench --bench lanes
+//! cargo bench --bench lanes --features avx2
+//! ```
+//!
+//! ```
+//! use spritz_cipher::lanes::{self, CipherLanes};
+//! use spritz_cipher::SpritzCipher;
+//!
+//! let mut lanes = CipherLanes::with_nonces([b"key 0", b"key 1"], [b"nonce 0", b"nonce 1"]);
+//! let mut a = *b"first session";
+//! let mut b = *b"second session";
+//! lanes.apply_keystream([&mut a, &mut b]);
+//!
+//! let mut expected = *b"second session";
+//! SpritzCipher::with_nonce(b"key 1", b"nonce 1").apply_keystream(&mut expected);
+//! assert_eq!(b, expected);
+//!
+//! let mut digests = [[0u8; 32]; 2];
+//! let [d0, d1] = &mut digests;
+//! lanes::hash([d0, d1], [b"arc", b"four"]).unwrap();
+//! ```
+
+use crate::sponge::Sponge;
+use crate::{LengthEncoding, SpritzCipherError, MAX_DIGEST_SIZE};
+
+#[cfg(feature = "std")]
+use std::io::IoSlice;
+
+/// How many lanes the batch functions use, the fastest width in `benches/lanes.rs`.
+const BATCH_LANES: usize = 4;
+
+/// `L` ciphers advanced together, each with its own key and nonce.
+pub struct CipherLanes<const L: usize> {
+    lanes: [Sponge; L],
+}
+
+impl<const L: usize> CipherLanes<L> {
+    /// Lane `l` is [`SpritzCipher::new`](crate::SpritzCipher::new) with `keys[l]`.
+    pub fn new(keys: [&[u8]; L]) -> CipherLanes<L> {
+        let mut lanes = core::array::from_fn(|_| Sponge::init());
+        Sponge::key_lanes(&mut lanes, keys, None);
+        CipherLanes { lanes }
+    }
+
+    /// Lane `l` is [`SpritzCipher::with_nonce`](crate::SpritzCipher::with_nonce) with `keys[l]`
+    /// and `nonces[l]`.
+    pub fn with_nonces(keys: [&[u8]; L], nonces: [&[u8]; L]) -> CipherLanes<L> {
+        let mut lanes = core::array::from_fn(|_| Sponge::init());
+        Sponge::key_lanes(&mut lanes, keys, Some(nonces));
+        CipherLanes { lanes }
+    }
+
+    /// Encrypt or decrypt `data[l]` in place with lane `l`.
+    pub fn apply_keystream(&mut self, mut data: [&mut [u8]; L]) {
+        Sponge::apply_keystream_lanes(&mut self.lanes, &mut data);
+    }
+}
+
+/// Hash `data[l]` into `digests[l]`, like [`SpritzHasher::hash`](crate::SpritzHasher::hash).
+///
+/// Returns an error, without hashing anything, if a digest is longer than [`MAX_DIGEST_SIZE`].
+pub fn hash<const L: usize>(digests: [&mut [u8]; L], data: [&[u8]; L]) -> Result<(), SpritzCipherError> {
+    hash_with(digests, data, LengthEncoding::Compat)
+}
+
+/// Hash `data[l]` into `digests[l]` with the given digest length encoding.
+pub fn hash_with<const L: usize>(
+    mut digests: [&mut [u8]; L],
+    data: [&[u8]; L],
+    encoding: LengthEncoding,
+) -> Result<(), SpritzCipherError> {
+    if encoding == LengthEncoding::Compat {
+        if let Some(digest) = digests.iter().find(|digest| digest.len() > MAX_DIGEST_SIZE) {
+            return Err(SpritzCipherError::DigestTooLong { max: MAX_DIGEST_SIZE, actual: digest.len() });
+        }
+    }
+
+    let mut lanes = core::array::from_fn(|_| Sponge::init());
+    Sponge::absorb_lanes(&mut lanes, data);
+    Sponge::squeeze_lanes(&mut lanes, &mut digests);
+    Ok(())
+}
+
+/// A message in a batch, hashed as the concatenation of its pieces.
+pub(crate) trait Pieces {
+    fn count(&self) -> usize;
+    fn piece(&self, k: usize) -> &[u8];
+}
+
+impl Pieces for &[u8] {
+    fn count(&self) -> usize {
+        1
+    }
+
+    fn piece(&self, _k: usize) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl Pieces for &[IoSlice<'_>] {
+    fn count(&self) -> usize {
+        self.len()
+    }
+
+    fn piece(&self, k: usize) -> &[u8] {
+        self.get(k).map_or(&[], |piece| &piece[..])
+    }
+}
+
+/// The keys of the messages in a batch.
+pub(crate) enum BatchKeys<'a> {
+    /// Hashes.
+    Unkeyed,
+    /// MACs under one key.
+    Shared(&'a [u8]),
+    /// MACs under a key per message.
+    PerMessage(&'a [&'a [u8]]),
+}
+
+impl BatchKeys<'_> {
+    fn get(&self, n: usize) -> Option<&[u8]> {
+        match self {
+            BatchKeys::Unkeyed => None,
+            BatchKeys::Shared(key) => Some(key),
+            BatchKeys::PerMessage(keys) => keys.get(n).copied(),
+        }
+    }
+}
+
+/// Hash or MAC each of `messages` into the next `len` bytes of `out`.
+///
+/// Full groups of messages go through the lanes, the rest one at a time.
+pub(crate) fn batch<M: Pieces>(out: &mut [u8], len: usize, messages: &[M], keys: BatchKeys<'_>) -> Result<(), SpritzCipherError> {
+    if len > MAX_DIGEST_SIZE {
+        return Err(SpritzCipherError::DigestTooLong { max: MAX_DIGEST_SIZE, actual: len });
+    }
+    let expected = messages.len().saturating_mul(len);
+    if out.len() != expected {
+        return Err(SpritzCipherError::LengthsDontMatch { expected, actual: out.len() });
+    }
+    if let BatchKeys::PerMessage(keys) = keys {
+        if keys.len() != messages.len() {
+            return Err(SpritzCipherError::LengthsDontMatch { expected: messages.len(), actual: keys.len() });
+        }
+    }
+    if len == 0 {
+        return Ok(());
+    }
+
+    let mut groups = messages.chunks_exact(BATCH_LANES);
+    let mut outs = out.chunks_exact_mut(len * BATCH_LANES);
+    for (n, (group, out)) in (&mut groups).zip(&mut outs).enumerate() {
+        let first = n * BATCH_LANES;
+        let mut lanes: [Sponge; BATCH_LANES] = core::array::from_fn(|_| Sponge::init());
+        if !matches!(keys, BatchKeys::Unkeyed) {
+            Sponge::absorb_lanes(&mut lanes, core::array::from_fn(|l| keys.get(first + l).unwrap_or(&[])));
+            Sponge::stop_lanes(&mut lanes, [true; BATCH_LANES]);
+        }
+        let most = group.iter().map(|message| message.count()).max().unwrap_or(0);
+        for k in 0..most {
+            Sponge::absorb_lanes(&mut lanes, core::array::from_fn(|l| group[l].piece(k)));
+        }
+
+        let mut digests = out.chunks_exact_mut(len);
+        let mut digests = core::array::from_fn(|_| digests.next().unwrap_or(&mut []));
+        Sponge::squeeze_lanes(&mut lanes, &mut digests);
+    }
+
+    let first = messages.len() - groups.remainder().len();
+    for (n, (message, digest)) in groups.remainder().iter().zip(outs.into_remainder().chunks_exact_mut(len)).enumerate() {
+        let mut sponge = Sponge::init();
+        if let Some(key) = keys.get(first + n) {
+            sponge.mac_key(key);
+        }
+        for k in 0..message.count() {
+            sponge.absorb_bytes(message.piece(k));
+        }
+        sponge.squeeze_extended(digest);
+    }
+    Ok(())
+}
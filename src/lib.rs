@@ -8,184 +8,206 @@
 //!
 //! The tests and examples have Encryption, Hash and MAC implementations. It requires no dependencies when used as a library.
 //! The tests and examples use rand and libc to compare against 2 different C implementations.
-//! 
-//! 
+//!
+//! # Usage
+//! Each use of Spritz has its own type, so only the operations that make sense are available:
+//! [`SpritzCipher`] for encryption, [`SpritzHasher`] and [`SpritzMac`] for hashes and tags,
+//! [`SpritzRng`] for random numbers. Finalising consumes the hasher or MAC.
+//!
+//! ```
+//! use spritz_cipher::{SpritzCipher, SpritzHasher, SpritzMac};
+//!
+//! let mut msg = *b"ABC";
+//! SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut msg);
+//!
+//! let mut digest = [0u8; 32];
+//! SpritzHasher::hash(&mut digest, &msg).unwrap();
+//!
+//! let mut mac = SpritzMac::new(b"key");
+//! mac.update(&msg);
+//! let mut tag = [0u8; 32];
+//! mac.finalize(&mut tag).unwrap();
+//! ```
+//!
+//! Keys, nonces, tags and digests can also be passed as [`SpritzKey`], [`Nonce`], [`Tag`] and
+//! [`Digest`], fixed size types that are wiped on drop and parse from hex or base64.
+//! Encrypting many messages under one key with different nonces is cheaper with a
+//! [`SpritzKeySchedule`], which absorbs the key once.
+//! Many independent sessions or hashes at once run faster through [`lanes`].
+//! Large inputs can be hashed on many cores with a [`tree`] hash, and encrypted with a
+//! [`SpritzCtr`], whose keystream can be seeked and split between threads.
+//! Long streams of the plain cipher can be resumed part way through from a [`checkpoint`].
+//! Files too large to authenticate in one piece can be encrypted in authenticated chunks with a
+//! [`stream`].
+//!
+//! [`SpritzCipherContext`] is the older all-in-one type, kept for compatibility.
+//!
 //! # Limitations
 //! Spritz is not as robust as other ciphers. There are known theortical attacks that may or may not allow an attacker to comprise the communication.
 //! Spritz is also slower than SHA3.
 //! That said, it is better than nothing
+//!
+//! # Features
+//! * `alloc` - the `*_boxed` constructors, which key a context directly on the heap.
+//! * `std` - implies `alloc`, enables the [`stats`] module, a statistical test battery for the keystream, and
+//!   implements `std::error::Error` for [`SpritzCipherError`]. On Linux it also adds `mem::SecureBox`,
+//!   locked and guard paged memory for contexts and keys, which uses `libc`. [`Secret`] keeps a
+//!   value encrypted in memory except while a closure is using it. `stream::StreamWriter` and
+//!   `stream::StreamReader` wrap `io::Write` and `io::Read`.
+//! * `parallel` - implies `std`, adds `tree::hash_parallel` and `SpritzCtr::apply_keystream_parallel`,
+//!   which run on rayon's thread pool.
+//! * `avx2` - implies `std`, on x86-64 [`lanes`] with a multiple of eight lanes use AVX2 gathers
+//!   when the CPU has them. This is slower than the portable lanes on the CPUs we have tried, see
+//!   the [`lanes`] docs.
+//! * `hardened` - every S-box lookup and swap with a key dependent index scans the whole table,
+//!   so the memory access pattern doesn't leak the index through the cache. This is a lot slower
+//!   and gives identical output, run the test suite with `--features hardened` to check.
+//! * `subtle` - conversions between [`ct::Choice`] and `subtle::Choice`.
+//!
+//! # Panics
+//! No public function panics, in debug or release builds. The state arithmetic wraps mod 256 as
+//! the spec requires and invalid input (mismatched lengths, a digest longer than
+//! [`MAX_DIGEST_SIZE`]) is returned as an error. `tests/panic_free.rs` covers the edge cases and
+//! the branch-free helpers have Kani proofs, run with `cargo kani`.
 
 #![no_std]
 
-const SPRITZ_N: usize = 256;
-const SPRITZ_N_MINUS_1: usize = SPRITZ_N - 1;
-const SPRITZ_N_HALF: usize = SPRITZ_N / 2;
-
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod stats;
+
+pub mod checkpoint;
+pub mod ct;
+pub mod encoding;
+pub mod lanes;
+pub mod mem;
+pub mod stream;
+pub mod tree;
+
+#[cfg(kani)]
+mod proofs;
+
+mod sponge;
+mod cipher;
+mod ctr;
+mod hash;
+mod mac;
+mod rng;
+mod types;
+#[cfg(feature = "std")]
+mod secret;
+
+pub use cipher::{SpritzCipher, SpritzKeySchedule};
+pub use ctr::SpritzCtr;
+pub use hash::SpritzHasher;
+pub use mac::SpritzMac;
+pub use rng::SpritzRng;
+pub use types::{Digest, Nonce, SpritzKey, Tag};
+#[cfg(feature = "std")]
+pub use secret::Secret;
+
+use core::fmt;
+use sponge::Sponge;
 use zeroize::Zeroize;
-#[derive(Debug)]
-pub enum SpritzCipherError {
-    LengthsDontMatch,
-}
-
-/// The primary structure that contains the buffer and varirables for the Cipher
-// This struct will be zeroized on drop
-#[derive(Zeroize)]
-#[zeroize(drop)]
-pub struct SpritzCipherContext {
-    //s-box
-    s: [u8; SPRITZ_N],
-    i: u8,
-    j: u8,
-    k: u8,
-    z: u8,
-    a: u8,
-    w: u8,
-    
-    tmp1: u8,
-    tmp2: u8,
 
+/// The largest digest (or MAC) that fits the one byte length Spritz absorbs.
+pub const MAX_DIGEST_SIZE: usize = 255;
+
+/// The shortest tag a MAC will verify, shorter ones are too easy to guess.
+pub const MIN_TAG_SIZE: usize = 16;
+
+/// How the digest length is absorbed before squeezing the digest out.
+///
+/// Up to [`MAX_DIGEST_SIZE`] bytes both encodings absorb the length as a single byte, the same
+/// as the reference implementation. Longer digests need [`LengthEncoding::Extended`], which
+/// absorbs a stop, the length in little-endian bytes (at least 2, no trailing zeros), another
+/// stop and then the number of length bytes. No two lengths give the same sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthEncoding {
+    /// Single byte only, digests longer than [`MAX_DIGEST_SIZE`] are an error.
+    Compat,
+    /// Any length, identical to `Compat` up to [`MAX_DIGEST_SIZE`].
+    Extended,
 }
 
-impl SpritzCipherContext {
-    fn init() -> SpritzCipherContext {
-
-        let mut context = SpritzCipherContext {
-            //s-box
-            s: [0;SPRITZ_N],
-            i: 0,
-            j: 0,
-            k: 0,
-            z: 0,
-            a: 0,
-            w: 1,
-
-            tmp1: 0,
-            tmp2: 0,
-
-        };
-
-        for (key,val) in context.s.iter_mut().enumerate(){
-            *val = key as u8;
-        }
-
-        context
-    }
-
-    fn state_s_swap(&mut self, index_a: u8, index_b: u8)
-    {
-        self.tmp1       = self.s[index_a as usize];
-        self.s[index_a as usize] = self.s[index_b as usize];
-        self.s[index_b as usize] = self.tmp1;
-    }
-
-    fn update(&mut self)
-    {
-        self.i = self.i.wrapping_add(self.w);
-        self.j = self.s[(self.s[self.i as usize].wrapping_add(self.j)) as usize].wrapping_add(self.k);
-        self.k = self.s[self.j as usize].wrapping_add(self.k).wrapping_add(self.i);
-        self.state_s_swap(self.i, self.j);
-    }
-
-    fn whip(&mut self)
-    {
-        for _i in 0..SPRITZ_N_HALF {
-            self.update();
-            self.update();
-            self.update();
-            self.update();
-        }
-
-        self.w = self.w + 2;
-    }
+/// Everything that can go wrong, with the lengths involved.
+///
+/// New variants may be added, so matches need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpritzCipherError {
+    /// Two buffers that must be the same length aren't, `actual` is the length of the second one.
+    LengthsDontMatch { expected: usize, actual: usize },
+    /// The digest is longer than `max` bytes, the most [`LengthEncoding::Compat`] can absorb.
+    DigestTooLong { max: usize, actual: usize },
+    /// A key, nonce, tag or digest of `expected` bytes was given `actual` bytes.
+    InvalidLength { expected: usize, actual: usize },
+    /// Hex or base64 input has a bad character (or the wrong length) at `position`.
+    InvalidEncoding { position: usize },
+    /// A parameter is below its smallest allowed value `min`.
+    TooSmall { min: usize, actual: usize },
+    /// A seekable keystream would go past its end at 2^64 bytes.
+    KeystreamExhausted,
+    /// A tag didn't match, the data was changed or the key is wrong.
+    AuthenticationFailed,
+}
 
-    #[inline(never)]
-    #[no_builtins]
-    #[cold]
-    //__attribute__ ((optnone))
-    fn crush(&mut self){
-        let mut j = SPRITZ_N_MINUS_1;
-        for i in 0..SPRITZ_N_HALF {
-            self.tmp1 = self.s[i]; /* s_i=self.s[i] */
-            self.tmp2 = self.s[j]; /* s_j=self.s[j] */
-            if self.tmp1 > self.tmp2 { /* if(s_i>s_j) */
-                self.s[i] = self.tmp2; /* self.s[i]=s_j */
-                self.s[j] = self.tmp1; /* self.s[j]=s_i */
+impl fmt::Display for SpritzCipherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpritzCipherError::LengthsDontMatch { expected, actual } => {
+                write!(f, "lengths don't match: expected {} bytes, got {}", expected, actual)
             }
-            else {
-                self.s[i] = self.tmp1; /* self.s[i]=s_i */
-                self.s[j] = self.tmp2; /* self.s[j]=s_j */
+            SpritzCipherError::DigestTooLong { max, actual } => {
+                write!(f, "digest too long: {} bytes, at most {} fit the length byte", actual, max)
+            }
+            SpritzCipherError::InvalidLength { expected, actual } => {
+                write!(f, "invalid length: expected {} bytes, got {}", expected, actual)
+            }
+            SpritzCipherError::InvalidEncoding { position } => {
+                write!(f, "invalid encoding at position {}", position)
+            }
+            SpritzCipherError::TooSmall { min, actual } => {
+                write!(f, "too small: {}, at least {} is needed", actual, min)
+            }
+            SpritzCipherError::KeystreamExhausted => {
+                write!(f, "keystream exhausted: the position would pass 2^64 bytes")
+            }
+            SpritzCipherError::AuthenticationFailed => {
+                write!(f, "authentication failed")
             }
-            j -= 1;
-        }
-    }
-
-    fn shuffle(&mut self)
-    {
-        self.whip();
-        self.crush();
-        self.whip();
-        self.crush();
-        self.whip();
-        self.a = 0;
-    }
-
-    fn absorb_nibble(&mut self, nibble: u8)
-    {
-        if self.a == SPRITZ_N_HALF as u8 {
-            self.shuffle();
-        }
-        self.state_s_swap(self.a, SPRITZ_N_HALF as u8 + nibble);
-        self.a += 1;
-    }
-
-    fn absorb(&mut self, octet: u8)
-    {
-        self.absorb_nibble(octet % 16); /* With the Right/Low nibble */
-        self.absorb_nibble(octet / 16); /* With the Left/High nibble */
-    }
-
-    fn absorb_bytes(&mut self, buf: &[u8])
-    {
-        //for byte in 0..buf.len {
-        for byte in buf.iter() {
-            self.absorb(*byte);
-        }
-    }
-
-    fn absorb_stop(&mut self)
-    {
-        if self.a == SPRITZ_N_HALF as u8 {
-            self.shuffle();
         }
-
-        self.a += 1;
     }
+}
 
-    //Types are screwed up here probably
-    fn output(&mut self) -> u8
-    {
-        self.z = self.s[
-                    (self.s[
-                        (self.s[
-                            (self.z.wrapping_add(self.k)) as usize % SPRITZ_N
-                        ] as usize + self.i as usize) % SPRITZ_N as usize
-                    ] as usize + self.j as usize) % SPRITZ_N
-                ];
-        return self.z;
-    }
+#[cfg(feature = "std")]
+impl std::error::Error for SpritzCipherError {}
 
+/// The primary structure that contains the buffer and varirables for the Cipher
+///
+/// Deprecated, it lets a hash be finalised twice or a cipher be fed data, which silently gives
+/// garbage. Use [`SpritzCipher`], [`SpritzHasher`], [`SpritzMac`] or [`SpritzRng`] instead,
+/// they only have the operations that make sense for them.
+// The state is zeroized on drop
+#[deprecated(note = "use `SpritzCipher`, `SpritzHasher`, `SpritzMac` or `SpritzRng`")]
+pub struct SpritzCipherContext {
+    sponge: Sponge,
+}
 
-    fn drip(&mut self) -> u8
-    {
-        if self.a > 0 {
-            self.shuffle();
-        }
-        self.update();
-        return self.output();
+#[allow(deprecated)]
+impl Zeroize for SpritzCipherContext {
+    fn zeroize(&mut self) {
+        self.sponge.zeroize();
     }
+}
 
-
-    
+#[allow(deprecated)]
+impl SpritzCipherContext {
     /// Timing-safe equality comparison for `data_a` and `data_b`.
     /// 
     /// This function can be used to compare the password's hash safely.
@@ -196,11 +218,14 @@ impl SpritzCipherContext {
     /// * Zero (0x00) if `data_a` equals `data_b`,
     /// * Non-zero value if they are NOT equal.
     /// * Error if the array lengths don't match
-    /// * Probably should be replaced by a bool (Thoughts?)
+    ///
+    /// Deprecated, `0` meaning equal is too easy to get backwards. Use [`ct::ct_eq`] which
+    /// returns `true` when the data is equal.
     ///
     /// # Examples
     ///
     /// ```
+    /// # #![allow(deprecated)]
     /// use spritz_cipher::SpritzCipherContext;
     /// const BUFFER_SIZE: usize = 24;
     /// let mut msg: [u8;BUFFER_SIZE] = ['A' as u8;BUFFER_SIZE];
@@ -211,13 +236,13 @@ impl SpritzCipherContext {
     /// assert_ne!(SpritzCipherContext::compare(&buf, &msg).unwrap(),0);
     /// ```
     /// 
+    #[deprecated(note = "use `ct::ct_eq`, it returns `true` when the data is equal")]
     #[inline(never)]
-    #[no_builtins]
     #[cold]
     pub fn compare(data_a: &[u8], data_b: &[u8]) -> Result<u8,SpritzCipherError>
     {
         if data_a.len() != data_b.len() {
-            return Err(SpritzCipherError::LengthsDontMatch);
+            return Err(SpritzCipherError::LengthsDontMatch { expected: data_a.len(), actual: data_b.len() });
         }
 
         let mut d = 0;
@@ -244,29 +269,6 @@ impl SpritzCipherContext {
     }
 
 
-    /// Clear the spritz cipher context by placing 0 in all locations.
-    /// 
-    /// To be replaced by Zeroize once I get it working.
-    /*#[inline(never)]
-    #[no_builtins]
-    #[cold]
-    pub fn state_memzero(&mut self)
-    {
-
-        self.s = [0;SPRITZ_N];
-
-        self.i = 0;
-        self.j = 0;
-        self.k = 0;
-        self.z = 0;
-        self.a = 0;
-        self.w = 0;
-
-        self.tmp1 = 0;
-        self.tmp2 = 0;
-
-    }*/
-   
 
     /// Setup the context with a key.
     /// * Parameter key:    The key.
@@ -274,12 +276,7 @@ impl SpritzCipherContext {
     /// * Return: A Context setup and ready to use.
     pub fn setup(key: &[u8]) -> SpritzCipherContext
     {
-        let mut context = SpritzCipherContext::init();
-        context.absorb_bytes(key);
-        if context.a > 0 {
-            context.shuffle();
-        }
-        context
+        SpritzCipherContext { sponge: Sponge::keyed(key, None) }
     }   
 
     /// Setup the context with a key and nonce/salt/iv.
@@ -290,14 +287,7 @@ impl SpritzCipherContext {
     #[allow(non_snake_case)]
     pub fn setup_with_IV(key: &[u8], nonce: &[u8]) -> SpritzCipherContext
     {
-        let mut context = SpritzCipherContext::init();
-        context.absorb_bytes(key);
-        context.absorb_stop();
-        context.absorb_bytes(nonce);
-        if context.a > 0 {
-            context.shuffle();
-        }
-        context
+        SpritzCipherContext { sponge: Sponge::keyed(key, Some(nonce)) }
     }
 
     /// Generates a random byte from the spritz context.
@@ -305,7 +295,7 @@ impl SpritzCipherContext {
     /// Probably shouldn't use this unless you need too
     pub fn random8(&mut self) -> u8
     {
-        self.drip()
+        self.sponge.drip()
     }
 
     /// Generates four random bytes from the spritz context.
@@ -313,11 +303,7 @@ impl SpritzCipherContext {
     /// Probably shouldn't use this unless you need too
     pub fn random32(&mut self) -> u32
     {
-        (
-        ((self.random8() as u32) <<  0)
-        | ((self.random8() as u32) <<  8)
-        | ((self.random8() as u32) << 16)
-        | ((self.random8() as u32) << 24))
+        self.sponge.random32()
     }
 
     //// Calculate an uniformly distributed random number less than `upper_bound` avoiding modulo bias.
@@ -338,32 +324,14 @@ impl SpritzCipherContext {
     /// Probably shouldn't use this unless you need too
     pub fn random32_uniform(&mut self, upper_bound: u32) -> u32
     {
-
-        if upper_bound < 2 {
-            return 0;
-        }
-
-        /* 2**32 % x == (2**32 - x) % x */
-        let min = (0-upper_bound) % upper_bound;
-
-        /* This could theoretically loop forever but each retry has
-        * p > 0.5 (worst case, usually far better) of selecting a
-        * number inside the range we need, so it should rarely need
-        * to re-roll.
-        */
-        loop {
-            let r = self.random32();
-            if r >= min {
-                return r % upper_bound;
-            }
-        }
+        self.sponge.random32_uniform(upper_bound)
     }
 
     /// Add entropy to the spritz context using absorb().
     /// * Parameter entropy: The entropy array.
     pub fn add_entropy(&mut self, entropy: &[u8])
     {
-        self.absorb_bytes(entropy);
+        self.sponge.absorb_bytes(entropy);
     }
 
     /// Encrypt or decrypt data chunk by XOR-ing it with the spritz keystream.
@@ -373,6 +341,7 @@ impl SpritzCipherContext {
     /// 
     /// Returns an error if the array lengths don't match
     /// ```
+    /// # #![allow(deprecated)]
     ///    use spritz_cipher::SpritzCipherContext;
     /// 
     ///    const BUFFER_SIZE: usize = 24;
@@ -391,64 +360,70 @@ impl SpritzCipherContext {
     ///     
     ///     //Encrypt
     ///     let mut context = SpritzCipherContext::setup(&key);
-    ///     context.crypt(&msg, &mut buf);
+    ///     context.crypt(&msg, &mut buf).unwrap();
     /// 
     ///     //Decrypt
     ///     let mut context = SpritzCipherContext::setup(&key);
     ///     let buf2 = buf.clone();
-    ///     context.crypt(&buf2, &mut buf);
+    ///     context.crypt(&buf2, &mut buf).unwrap();
     /// 
     ///     /* Check the output */
-    ///     assert_eq!(SpritzCipherContext::compare(&buf, &msg).unwrap(), 0);
+    ///     assert!(spritz_cipher::ct::ct_eq(&buf, &msg));
     ///  ```
     pub fn crypt(&mut self, data: &[u8], data_out: &mut [u8]) -> Result<(),SpritzCipherError>
     {
-
-        if data.len() != data_out.len() {
-            return Err(SpritzCipherError::LengthsDontMatch);
-        }
-
-        for (i,byte) in data.iter().enumerate() {
-            data_out[i] = byte ^ self.drip();
-        }
-
-        Ok(())
+        self.sponge.crypt(data, data_out)
     }
 
 
     /// Setup the spritz hash context.
     /// * Return: A Context setup and ready to use.
     pub fn hash_setup() -> SpritzCipherContext {
-        SpritzCipherContext::init()
+        SpritzCipherContext { sponge: Sponge::init() }
     }
 
     /// Add a message/data chunk `data` to hash.
     /// * Parameter data:     The data chunk to hash.
     pub fn hash_update(&mut self, data: &[u8]) {
-        self.absorb_bytes(data);
+        self.sponge.absorb_bytes(data);
     }
 
     /// Output the hash digest.
     /// * Parameter digest:    The digest (hash) output.
-    pub fn hash_final(&mut self, digest: &mut [u8])
+    ///
+    /// Returns an error if the digest is longer than [`MAX_DIGEST_SIZE`] bytes, the context is left as it was.
+    /// Use [`hash_final_with`](Self::hash_final_with) and [`LengthEncoding::Extended`] for longer digests.
+    pub fn hash_final(&mut self, digest: &mut [u8]) -> Result<(),SpritzCipherError>
     {
+        self.hash_final_with(digest, LengthEncoding::Compat)
+    }
 
-        self.absorb_stop();
-        self.absorb(digest.len() as u8);
-        /* squeeze() */
-        if self.a > 0 {
-            self.shuffle();
-        }
-
-        for byte in digest.iter_mut() {
-            *byte = self.drip();
-        }
+    /// Output the hash digest, absorbing its length with `encoding`.
+    /// * Parameter digest:    The digest (hash) output.
+    /// * Parameter encoding:  How the length of `digest` is absorbed.
+    ///
+    /// Returns an error if `encoding` is [`LengthEncoding::Compat`] and the digest is longer than
+    /// [`MAX_DIGEST_SIZE`] bytes, the context is left as it was.
+    /// ```
+    /// # #![allow(deprecated)]
+    /// use spritz_cipher::{LengthEncoding, SpritzCipherContext};
+    ///
+    /// let mut digest = [0u8; 512];
+    /// let mut context = SpritzCipherContext::hash_setup();
+    /// context.hash_update(b"arcfour");
+    /// assert!(context.hash_final(&mut digest).is_err());
+    /// context.hash_final_with(&mut digest, LengthEncoding::Extended).unwrap();
+    /// ```
+    pub fn hash_final_with(&mut self, digest: &mut [u8], encoding: LengthEncoding) -> Result<(),SpritzCipherError>
+    {
+        self.sponge.squeeze(digest, encoding)
     }
 
     //// Cryptographic hash function.
     /// * Parameter digest:    The digest (hash) output.
     /// * Parameter data:      The data to hash.
     /// ```
+    /// # #![allow(deprecated)]
     /// use spritz_cipher::SpritzCipherContext;
     /// 
     ///    const BUFFER_SIZE: usize = 32;
@@ -469,26 +444,41 @@ impl SpritzCipherContext {
     ///    for byte in test_data.iter() {
     ///        context.hash_update(&[*byte]);
     ///    }
-    ///    context.hash_final(&mut digest_2);
+    ///    context.hash_final(&mut digest_2).unwrap();
     /// 
     ///    //Short cut the above steps by doing it all in one hit
-    ///    SpritzCipherContext::hash(&mut digest, &test_data);
+    ///    SpritzCipherContext::hash(&mut digest, &test_data).unwrap();
     /// 
     ///    /* Check the output */
-    ///    assert_eq!(SpritzCipherContext::compare(&digest, &test_vector).unwrap(), 0);
+    ///    assert!(spritz_cipher::ct::ct_eq(&digest, &test_vector));
     /// 
-    ///    assert_eq!(SpritzCipherContext::compare(&digest_2, &test_vector).unwrap(), 0);
+    ///    assert!(spritz_cipher::ct::ct_eq(&digest_2, &test_vector));
     /// ```
-    pub fn hash(digest: &mut [u8], data: &[u8])
+    pub fn hash(digest: &mut [u8], data: &[u8]) -> Result<(),SpritzCipherError>
     {
 
         let mut context = SpritzCipherContext::hash_setup(); /* spritz_state_init() */
         context.hash_update(data); /* absorbBytes() */
-        context.hash_final(digest);
+        let result = context.hash_final(digest);
 
         //context.state_memzero();
         context.zeroize();
-        
+
+        result
+    }
+
+    /// Cryptographic hash function, absorbing the digest length with `encoding`.
+    /// * Parameter digest:    The digest (hash) output.
+    /// * Parameter data:      The data to hash.
+    /// * Parameter encoding:  How the length of `digest` is absorbed.
+    pub fn hash_with(digest: &mut [u8], data: &[u8], encoding: LengthEncoding) -> Result<(),SpritzCipherError>
+    {
+        let mut context = SpritzCipherContext::hash_setup();
+        context.hash_update(data);
+        let result = context.hash_final_with(digest, encoding);
+        context.zeroize();
+
+        result
     }
 
 
@@ -498,10 +488,7 @@ impl SpritzCipherContext {
     /// * Return: A Context setup and ready to use.
     pub fn mac_setup(key : &[u8]) -> SpritzCipherContext
     {
-        let mut context = SpritzCipherContext::hash_setup(); /* spritz_state_init() */
-        context.hash_update(key); /* absorbBytes() */
-        context.absorb_stop();
-        context
+        SpritzCipherContext { sponge: Sponge::mac_keyed(key) }
     }
 
     //// Add a message/data chunk to message authentication code (MAC).
@@ -513,9 +500,22 @@ impl SpritzCipherContext {
 
     /// Output the message authentication code (MAC) digest.
     /// * Parameter digest:    Message authentication code (MAC) digest output.
-    pub fn mac_final(&mut self, digest: &mut [u8])
+    ///
+    /// Returns an error if the digest is longer than [`MAX_DIGEST_SIZE`] bytes.
+    pub fn mac_final(&mut self, digest: &mut [u8]) -> Result<(),SpritzCipherError>
+    {
+        self.hash_final(digest)
+    }
+
+    /// Output the message authentication code (MAC) digest, absorbing its length with `encoding`.
+    /// * Parameter digest:    Message authentication code (MAC) digest output.
+    /// * Parameter encoding:  How the length of `digest` is absorbed.
+    ///
+    /// Returns an error if `encoding` is [`LengthEncoding::Compat`] and the digest is longer than
+    /// [`MAX_DIGEST_SIZE`] bytes.
+    pub fn mac_final_with(&mut self, digest: &mut [u8], encoding: LengthEncoding) -> Result<(),SpritzCipherError>
     {
-        self.hash_final(digest);
+        self.hash_final_with(digest, encoding)
     }
 
     /// Message Authentication Code (MAC) function.
@@ -523,6 +523,7 @@ impl SpritzCipherContext {
     /// * Parameter msg:       The message to be authenticated.
     /// * Parameter key:       The secret key.
     /// ```
+    /// # #![allow(deprecated)]
     ///     /* Data to input */
     ///    let mut msg: [u8;3] = ['A' as u8, 'B' as u8, 'C' as u8];
     ///    let mut key: [u8;3] = [0x00, 0x01, 0x02];
@@ -538,20 +539,35 @@ impl SpritzCipherContext {
     ///    let mut digest = [0u8;BUFFER_SIZE]; /* Output buffer */
     /// 
     ///    use spritz_cipher::SpritzCipherContext;
-    ///    SpritzCipherContext::mac(&mut digest, &mut msg, &mut key);
+    ///    SpritzCipherContext::mac(&mut digest, &mut msg, &mut key).unwrap();
     /// 
     ///    /* Check the output */
-    ///    assert_eq!(SpritzCipherContext::compare(&digest, &test_vector).unwrap(), 0);
+    ///    assert!(spritz_cipher::ct::ct_eq(&digest, &test_vector));
     /// ```
-    pub fn mac(digest: &mut[u8], msg: &mut[u8], key: &mut[u8]) {
+    pub fn mac(digest: &mut[u8], msg: &mut[u8], key: &mut[u8]) -> Result<(),SpritzCipherError> {
 
         let mut context = SpritzCipherContext::mac_setup(key);
         context.mac_update(msg); /* absorbBytes() */
-        context.mac_final(digest);
+        let result = context.mac_final(digest);
 
         //context.state_memzero();
         context.zeroize();
-        
+
+        result
+    }
+
+    /// Message Authentication Code (MAC) function, absorbing the digest length with `encoding`.
+    /// * Parameter digest:    Message authentication code (MAC) digest output.
+    /// * Parameter msg:       The message to be authenticated.
+    /// * Parameter key:       The secret key.
+    /// * Parameter encoding:  How the length of `digest` is absorbed.
+    pub fn mac_with(digest: &mut [u8], msg: &[u8], key: &[u8], encoding: LengthEncoding) -> Result<(),SpritzCipherError> {
+        let mut context = SpritzCipherContext::mac_setup(key);
+        context.mac_update(msg);
+        let result = context.mac_final_with(digest, encoding);
+        context.zeroize();
+
+        result
     }
 
 
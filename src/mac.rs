@@ -0,0 +1,185 @@
+//! The Spritz message authentication code.
+
+use crate::lanes::{self, BatchKeys};
+use crate::mem::InPlace;
+use crate::sponge::Sponge;
+use crate::{ct, LengthEncoding, SpritzCipherError, SpritzKey, Tag, MAX_DIGEST_SIZE, MIN_TAG_SIZE};
+
+use core::mem::MaybeUninit;
+#[cfg(feature = "std")]
+use std::io::IoSlice;
+use zeroize::Zeroize;
+
+/// An incremental Spritz MAC.
+///
+/// [`finalize`](Self::finalize) and [`verify`](Self::verify) consume the MAC, so a tag can
+/// only be produced once.
+/// ```
+/// use spritz_cipher::SpritzMac;
+///
+/// let mut mac = SpritzMac::new(&[0x00, 0x01, 0x02]);
+/// mac.update(b"ABC");
+///
+/// let mut tag = [0u8; 32];
+/// mac.finalize(&mut tag).unwrap();
+/// assert_eq!(&tag[..4], &[0xbe, 0x8e, 0xdc, 0xf2]);
+///
+/// let mut mac = SpritzMac::new(&[0x00, 0x01, 0x02]);
+/// mac.update(b"ABC");
+/// assert!(mac.verify(&tag));
+/// ```
+pub struct SpritzMac {
+    sponge: Sponge,
+}
+
+impl SpritzMac {
+    /// Setup the MAC with a secret key.
+    pub fn new(key: &[u8]) -> SpritzMac {
+        SpritzMac { sponge: Sponge::mac_keyed(key) }
+    }
+
+    /// Setup the MAC with a secret key in `slot`, without the state ever being moved.
+    pub fn new_in<'a>(slot: &'a mut MaybeUninit<SpritzMac>, key: &[u8]) -> InPlace<'a, SpritzMac> {
+        let mut mac = InPlace::new(slot, SpritzMac { sponge: Sponge::init() });
+        mac.sponge.mac_key(key);
+        mac
+    }
+
+    /// Setup the MAC with a typed key.
+    pub fn from_key<const K: usize>(key: &SpritzKey<K>) -> SpritzMac {
+        SpritzMac::new(key.as_bytes())
+    }
+
+    /// Add a message chunk `msg` to be authenticated.
+    pub fn update(&mut self, msg: &[u8]) {
+        self.sponge.absorb_bytes(msg);
+    }
+
+    /// Add scattered buffers to the message, the same as adding each in turn.
+    #[cfg(feature = "std")]
+    pub fn update_vectored(&mut self, bufs: &[IoSlice<'_>]) {
+        for buf in bufs {
+            self.sponge.absorb_bytes(buf);
+        }
+    }
+
+    /// Output the tag, its length is `tag.len()`.
+    ///
+    /// Returns an error if the tag is longer than [`MAX_DIGEST_SIZE`]
+    /// bytes, use [`finalize_with`](Self::finalize_with) for longer tags.
+    pub fn finalize(self, tag: &mut [u8]) -> Result<(), SpritzCipherError> {
+        self.finalize_with(tag, LengthEncoding::Compat)
+    }
+
+    /// Output the tag, absorbing its length with `encoding`.
+    ///
+    /// Never fails with [`LengthEncoding::Extended`].
+    pub fn finalize_with(mut self, tag: &mut [u8], encoding: LengthEncoding) -> Result<(), SpritzCipherError> {
+        self.sponge.squeeze(tag, encoding)
+    }
+
+    /// Check a received tag in constant time, `false` if it is shorter than [`MIN_TAG_SIZE`]
+    /// or longer than [`MAX_DIGEST_SIZE`].
+    pub fn verify(mut self, tag: &[u8]) -> bool {
+        self.verify_in_place(tag)
+    }
+
+    fn verify_in_place(&mut self, tag: &[u8]) -> bool {
+        /* An empty tag would always match, a stripped tag must not authenticate */
+        if tag.len() < MIN_TAG_SIZE {
+            return false;
+        }
+        let mut buf = [0u8; MAX_DIGEST_SIZE];
+        let equal = match buf.get_mut(..tag.len()) {
+            Some(expected) => self.sponge.squeeze(expected, LengthEncoding::Compat).is_ok() && ct::ct_eq(expected, tag),
+            None => false,
+        };
+        buf.zeroize();
+        equal
+    }
+
+    /// Output an `N` byte tag, any `N` works (see [`LengthEncoding::Extended`]).
+    pub fn finalize_tag<const N: usize>(mut self) -> Tag<N> {
+        Tag::squeeze(|out| self.sponge.squeeze_extended(out))
+    }
+
+    /// Check a received typed tag in constant time, `false` if `N` is less than
+    /// [`MIN_TAG_SIZE`].
+    /// ```
+    /// use spritz_cipher::{SpritzKey, SpritzMac, Tag};
+    ///
+    /// let key = SpritzKey::<3>::new([0x00, 0x01, 0x02]);
+    /// let tag: Tag = SpritzMac::tag(b"ABC", &key);
+    /// assert_eq!(tag.to_hex().to_string(), "be8edcf276cf57b40ebc8e2243457e3eb7c64d4e991e9358ce81efb16ccec7ed");
+    ///
+    /// let mut mac = SpritzMac::from_key(&key);
+    /// mac.update(b"ABC");
+    /// assert!(mac.verify_tag(&tag));
+    /// ```
+    pub fn verify_tag<const N: usize>(self, tag: &Tag<N>) -> bool {
+        N >= MIN_TAG_SIZE && self.finalize_tag::<N>() == *tag
+    }
+
+    /// The `N` byte tag of `msg` under `key`.
+    pub fn tag<const N: usize, const K: usize>(msg: &[u8], key: &SpritzKey<K>) -> Tag<N> {
+        let mut mac = SpritzMac::from_key(key);
+        mac.update(msg);
+        mac.finalize_tag()
+    }
+
+    /// Authenticate `msg` with `key` in one go.
+    pub fn mac(tag: &mut [u8], msg: &[u8], key: &[u8]) -> Result<(), SpritzCipherError> {
+        SpritzMac::mac_with(tag, msg, key, LengthEncoding::Compat)
+    }
+
+    /// Authenticate `msg` with `key` in one go, absorbing the tag length with `encoding`.
+    pub fn mac_with(tag: &mut [u8], msg: &[u8], key: &[u8], encoding: LengthEncoding) -> Result<(), SpritzCipherError> {
+        let mut mac = SpritzMac::new(key);
+        mac.update(msg);
+        mac.finalize_with(tag, encoding)
+    }
+
+    /// Authenticate each of `messages` with `key`, writing the tags to the next `tag_len` bytes
+    /// of `out`.
+    ///
+    /// The same tags as [`mac`](Self::mac) on each message, computed several at a time with
+    /// [`lanes`](crate::lanes). `out` must be exactly `messages.len() * tag_len` bytes.
+    pub fn mac_batch(out: &mut [u8], tag_len: usize, messages: &[&[u8]], key: &[u8]) -> Result<(), SpritzCipherError> {
+        lanes::batch(out, tag_len, messages, BatchKeys::Shared(key))
+    }
+
+    /// [`mac_batch`](Self::mac_batch) with a key per message, `keys` must be as long as `messages`.
+    /// ```
+    /// use spritz_cipher::SpritzMac;
+    ///
+    /// let messages: [&[u8]; 2] = [b"to alice", b"to bob"];
+    /// let keys: [&[u8]; 2] = [b"alice's key", b"bob's key"];
+    /// let mut tags = [0u8; 2 * 32];
+    /// SpritzMac::mac_batch_keyed(&mut tags, 32, &messages, &keys).unwrap();
+    ///
+    /// let mut expected = [0u8; 32];
+    /// SpritzMac::mac(&mut expected, b"to bob", b"bob's key").unwrap();
+    /// assert_eq!(tags[32..], expected);
+    /// ```
+    pub fn mac_batch_keyed(out: &mut [u8], tag_len: usize, messages: &[&[u8]], keys: &[&[u8]]) -> Result<(), SpritzCipherError> {
+        lanes::batch(out, tag_len, messages, BatchKeys::PerMessage(keys))
+    }
+}
+
+/// The finalisers of a MAC made with [`SpritzMac::new_in`], they wipe the slot afterwards.
+impl InPlace<'_, SpritzMac> {
+    /// [`SpritzMac::finalize`] without moving the state out of its slot.
+    pub fn finalize(self, tag: &mut [u8]) -> Result<(), SpritzCipherError> {
+        self.finalize_with(tag, LengthEncoding::Compat)
+    }
+
+    /// [`SpritzMac::finalize_with`] without moving the state out of its slot.
+    pub fn finalize_with(mut self, tag: &mut [u8], encoding: LengthEncoding) -> Result<(), SpritzCipherError> {
+        self.sponge.squeeze(tag, encoding)
+    }
+
+    /// [`SpritzMac::verify`] without moving the state out of its slot.
+    pub fn verify(mut self, tag: &[u8]) -> bool {
+        self.verify_in_place(tag)
+    }
+}
@@ -0,0 +1,86 @@
+//! Keeping key dependent state from being copied around memory.
+//!
+//! Returning a context by value can leave copies of its S-box in dead stack frames, where
+//! zeroizing on drop can't reach them. The `*_in` constructors (such as
+//! [`SpritzCipher::new_in`](crate::SpritzCipher::new_in)) key a context directly in a slot the
+//! caller provides, the `*_boxed` ones (with the `alloc` feature) directly on the heap. Only
+//! the unkeyed starting state, which is the same for every key, is ever moved.
+//! An in-place hash or MAC is finalised through its [`InPlace`], which has its own `finalize`
+//! so the state isn't moved out of the slot to be consumed.
+//!
+//! The contexts hold plain bytes and are `Unpin`, so `Pin` adds nothing: an [`InPlace`] never
+//! moves its value, and `Box::into_pin` works on the boxed constructors if an API wants a
+//! `Pin<Box<_>>`.
+//!
+//! With `std` on Linux, [`SecureBox`] goes further and keeps a context or key in locked memory
+//! that is never swapped out or written to a core dump.
+//!
+//! ```
+//! use core::mem::MaybeUninit;
+//! use spritz_cipher::SpritzCipher;
+//!
+//! let mut slot = MaybeUninit::uninit();
+//! let mut cipher = SpritzCipher::new_in(&mut slot, b"key");
+//!
+//! let mut msg = *b"ABC";
+//! cipher.apply_keystream(&mut msg);
+//! /* Dropping `cipher` wipes the slot */
+//! ```
+
+use core::hint::black_box;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use zeroize::Zeroize;
+
+#[cfg(all(feature = "std", target_os = "linux"))]
+mod secure_box;
+#[cfg(all(feature = "std", target_os = "linux"))]
+pub use secure_box::SecureBox;
+
+/// A value living in a caller provided [`MaybeUninit`] slot, dropped (and so wiped) in place.
+///
+/// Forgetting it with `mem::forget` skips the wipe, like forgetting any other value.
+pub struct InPlace<'a, T> {
+    value: &'a mut T,
+}
+
+impl<'a, T> InPlace<'a, T> {
+    /// Move `value` into `slot`, it must not hold secrets yet.
+    pub(crate) fn new(slot: &'a mut MaybeUninit<T>, value: T) -> InPlace<'a, T> {
+        InPlace { value: slot.write(value) }
+    }
+}
+
+impl<T> Deref for InPlace<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T> DerefMut for InPlace<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+impl<T> Drop for InPlace<'_, T> {
+    fn drop(&mut self) {
+        /* The slot is a MaybeUninit, so nothing else will drop the value */
+        unsafe { ptr::drop_in_place(self.value as *mut T) }
+    }
+}
+
+/// Overwrite `N` bytes of the stack below the caller with zeros.
+///
+/// Functions that handled secrets by value leave them in the stack space they have returned
+/// from. Call this afterwards with `N` larger than the stack those calls used, it needs `N`
+/// bytes of free stack itself.
+#[inline(never)]
+pub fn scrub_stack<const N: usize>() {
+    let mut buf = [0u8; N];
+    buf.zeroize();
+    black_box(&buf);
+}
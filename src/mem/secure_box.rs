@@ -0,0 +1,188 @@
+//! Page locked, guard paged storage (`std` on Linux).
+
+use super::InPlace;
+use crate::sponge::Sponge;
+#[allow(deprecated)]
+use crate::SpritzCipherContext;
+
+use core::marker::PhantomData;
+use core::mem::{self, align_of, size_of, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::{fmt, ptr, slice};
+use std::io;
+use zeroize::Zeroize;
+
+/// An anonymous mapping: a guard page, `len` locked bytes, another guard page.
+struct Pages {
+    base: *mut u8,
+    page: usize,
+    len: usize,
+}
+
+impl Pages {
+    /// Map enough locked pages for `size` bytes, they start out zeroed.
+    fn map(size: usize, align: usize) -> io::Result<Pages> {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if page <= 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let page = page as usize;
+        if align > page {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "alignment is larger than a page"));
+        }
+        let too_big = || io::Error::new(io::ErrorKind::InvalidInput, "too large to map");
+        let len = size.max(1).checked_add(page - 1).ok_or_else(too_big)? / page * page;
+        let total = len.checked_add(2 * page).ok_or_else(too_big)?;
+
+        /* Everything starts inaccessible, then the middle is opened up */
+        let base = unsafe {
+            libc::mmap(ptr::null_mut(), total, libc::PROT_NONE, libc::MAP_PRIVATE | libc::MAP_ANONYMOUS, -1, 0)
+        };
+        if base == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let base = base as *mut u8;
+
+        let data = unsafe { base.add(page) } as *mut libc::c_void;
+        if unsafe { libc::mprotect(data, len, libc::PROT_READ | libc::PROT_WRITE) } != 0 {
+            /* Not a `Pages` yet, its drop would wipe pages that can't be written */
+            let err = io::Error::last_os_error();
+            unsafe { libc::munmap(base as *mut libc::c_void, total) };
+            return Err(err);
+        }
+        let pages = Pages { base, page, len };
+
+        unsafe {
+            if libc::madvise(data, len, libc::MADV_DONTDUMP) != 0 || libc::mlock(data, len) != 0 {
+                /* Dropping `pages` unmaps them */
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(pages)
+    }
+
+    /// The start of the accessible pages.
+    fn data(&self) -> *mut u8 {
+        unsafe { self.base.add(self.page) }
+    }
+}
+
+impl Drop for Pages {
+    fn drop(&mut self) {
+        unsafe {
+            /* Only made once the middle is read/write, even if locking them failed */
+            let data = slice::from_raw_parts_mut(self.data(), self.len);
+            data.zeroize();
+            libc::munlock(data.as_ptr() as *const libc::c_void, self.len);
+            libc::munmap(self.base as *mut libc::c_void, self.len + 2 * self.page);
+        }
+    }
+}
+
+/// A value in its own `mlock`ed pages, kept out of swap and core dumps.
+///
+/// The value sits at the end of its pages, right before an inaccessible guard page, with
+/// another guard page in front. The pages are marked `MADV_DONTDUMP` and wiped after the value
+/// is dropped. Each box locks at least one page, and how much a process may lock is limited by
+/// `RLIMIT_MEMLOCK` (`ulimit -l`), going over that is an error when the box is made.
+///
+/// ```
+/// use spritz_cipher::mem::SecureBox;
+/// use spritz_cipher::SpritzCipher;
+///
+/// let mut key = SecureBox::new([0u8; 32]).unwrap();
+/// key.copy_from_slice(b"a key read straight into the box");
+///
+/// let mut cipher = SecureBox::new_with(|slot| SpritzCipher::new_in(slot, &*key)).unwrap();
+/// let mut msg = *b"ABC";
+/// cipher.apply_keystream(&mut msg);
+/// ```
+pub struct SecureBox<T> {
+    pages: Pages,
+    value: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for SecureBox<T> {}
+unsafe impl<T: Sync> Sync for SecureBox<T> {}
+
+/// Where a `T` lives in `pages`, at the end so overruns hit the guard page.
+fn slot<T>(pages: &Pages) -> *mut T {
+    /* The size is a multiple of the alignment, so the end stays aligned */
+    unsafe { pages.data().add(pages.len - size_of::<T>()) as *mut T }
+}
+
+impl<T> SecureBox<T> {
+    /// Move `value` into locked pages.
+    ///
+    /// Moving can leave a copy behind where `value` came from, so only move in things that hold
+    /// no secrets yet, like a zeroed key buffer, and fill them in the box.
+    pub fn new(value: T) -> io::Result<SecureBox<T>> {
+        let pages = Pages::map(size_of::<T>(), align_of::<T>())?;
+        unsafe { slot::<T>(&pages).write(value) };
+        Ok(SecureBox { pages, value: PhantomData })
+    }
+
+    /// Build the value directly in locked pages with one of the `*_in` constructors.
+    ///
+    /// `init` must return the [`InPlace`] for the slot it is given, anything else is an
+    /// `InvalidInput` error.
+    pub fn new_with<F>(init: F) -> io::Result<SecureBox<T>>
+    where
+        F: for<'a> FnOnce(&'a mut MaybeUninit<T>) -> InPlace<'a, T>,
+    {
+        let pages = Pages::map(size_of::<T>(), align_of::<T>())?;
+        let value = slot::<T>(&pages);
+        let built = init(unsafe { &mut *(value as *mut MaybeUninit<T>) });
+        if !ptr::eq(&*built, value) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "initialised a different slot"));
+        }
+        /* The box drops the value from now on */
+        mem::forget(built);
+        Ok(SecureBox { pages, value: PhantomData })
+    }
+}
+
+#[allow(deprecated)]
+impl SecureBox<SpritzCipherContext> {
+    /// [`SpritzCipherContext::setup`], keyed in locked pages.
+    pub fn setup(key: &[u8]) -> io::Result<SecureBox<SpritzCipherContext>> {
+        let mut context = SecureBox::new(SpritzCipherContext { sponge: Sponge::init() })?;
+        context.sponge.key(key, None);
+        Ok(context)
+    }
+
+    /// [`SpritzCipherContext::setup_with_IV`], keyed in locked pages.
+    #[allow(non_snake_case)]
+    pub fn setup_with_IV(key: &[u8], nonce: &[u8]) -> io::Result<SecureBox<SpritzCipherContext>> {
+        let mut context = SecureBox::new(SpritzCipherContext { sponge: Sponge::init() })?;
+        context.sponge.key(key, Some(nonce));
+        Ok(context)
+    }
+}
+
+impl<T> Deref for SecureBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*slot::<T>(&self.pages) }
+    }
+}
+
+impl<T> DerefMut for SecureBox<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *slot::<T>(&self.pages) }
+    }
+}
+
+impl<T> Drop for SecureBox<T> {
+    fn drop(&mut self) {
+        /* `pages` is wiped and unmapped after this */
+        unsafe { ptr::drop_in_place(slot::<T>(&self.pages)) }
+    }
+}
+
+impl<T> fmt::Debug for SecureBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecureBox(..)")
+    }
+}
@@ -0,0 +1,47 @@
+//! Kani proof harnesses, run with `cargo kani`.
+//!
+//! The sponge itself loops too much for a model checker, these cover the branch-free
+//! helpers the rest of the crate relies on.
+
+use crate::ct::{ConditionallySelectable, ConstantTimeEq, Choice};
+use crate::sponge::Sponge;
+
+#[kani::proof]
+fn ct_gt_mask_is_greater_than() {
+    let a: u8 = kani::any();
+    let b: u8 = kani::any();
+    let expected = if a > b { 0xff } else { 0x00 };
+    assert_eq!(Sponge::ct_gt_mask(a, b), expected);
+}
+
+#[kani::proof]
+fn ct_eq_is_equality() {
+    let a: u32 = kani::any();
+    let b: u32 = kani::any();
+    assert_eq!(bool::from(a.ct_eq(&b)), a == b);
+
+    let a: u8 = kani::any();
+    let b: u8 = kani::any();
+    assert_eq!(bool::from(a.ct_eq(&b)), a == b);
+}
+
+#[kani::proof]
+fn conditional_select_selects() {
+    let a: u64 = kani::any();
+    let b: u64 = kani::any();
+    let choice: bool = kani::any();
+    let selected = u64::conditional_select(&a, &b, Choice::from(choice as u8));
+    assert_eq!(selected, if choice { b } else { a });
+}
+
+#[kani::proof]
+fn uniform_bound_doesnt_overflow() {
+    /* The rejection threshold in random32_uniform */
+    let upper_bound: u32 = kani::any();
+    kani::assume(upper_bound >= 2);
+    let min = Sponge::uniform_min(upper_bound);
+    assert!(min < upper_bound);
+    assert_eq!(min as u64, (1u64 << 32) % upper_bound as u64);
+    /* The kept draws, min..2**32, are whole multiples of upper_bound */
+    assert_eq!(((1u64 << 32) - min as u64) % upper_bound as u64, 0);
+}
@@ -0,0 +1,94 @@
+//! Random numbers from the Spritz keystream.
+
+use crate::mem::InPlace;
+use crate::sponge::Sponge;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+use core::mem::MaybeUninit;
+
+/// A deterministic random number generator seeded with a key.
+///
+/// The same seed always gives the same numbers. More entropy can be mixed in at any time with
+/// [`add_entropy`](Self::add_entropy).
+/// ```
+/// use spritz_cipher::SpritzRng;
+///
+/// let mut rng = SpritzRng::new(b"ABC");
+/// assert_eq!(rng.random32(), 0x018e9a77);
+///
+/// let dice = rng.random32_uniform(6) + 1;
+/// assert!((1..=6).contains(&dice));
+/// ```
+pub struct SpritzRng {
+    sponge: Sponge,
+}
+
+impl SpritzRng {
+    /// Setup the generator with a seed.
+    pub fn new(seed: &[u8]) -> SpritzRng {
+        SpritzRng { sponge: Sponge::keyed(seed, None) }
+    }
+
+    /// Setup the generator with a seed and a nonce, for several streams from one seed.
+    pub fn with_nonce(seed: &[u8], nonce: &[u8]) -> SpritzRng {
+        SpritzRng { sponge: Sponge::keyed(seed, Some(nonce)) }
+    }
+
+    /// Setup the generator with a seed in `slot`, without the state ever being moved.
+    pub fn new_in<'a>(slot: &'a mut MaybeUninit<SpritzRng>, seed: &[u8]) -> InPlace<'a, SpritzRng> {
+        let mut rng = InPlace::new(slot, SpritzRng { sponge: Sponge::init() });
+        rng.sponge.key(seed, None);
+        rng
+    }
+
+    /// Setup the generator with a seed and a nonce in `slot`, without the state ever being moved.
+    pub fn with_nonce_in<'a>(slot: &'a mut MaybeUninit<SpritzRng>, seed: &[u8], nonce: &[u8]) -> InPlace<'a, SpritzRng> {
+        let mut rng = InPlace::new(slot, SpritzRng { sponge: Sponge::init() });
+        rng.sponge.key(seed, Some(nonce));
+        rng
+    }
+
+    /// Setup the generator with a seed on the heap, without the state ever being moved.
+    #[cfg(feature = "alloc")]
+    pub fn new_boxed(seed: &[u8]) -> Box<SpritzRng> {
+        let mut rng = Box::new(SpritzRng { sponge: Sponge::init() });
+        rng.sponge.key(seed, None);
+        rng
+    }
+
+    /// Setup the generator with a seed and a nonce on the heap, without the state ever being moved.
+    #[cfg(feature = "alloc")]
+    pub fn with_nonce_boxed(seed: &[u8], nonce: &[u8]) -> Box<SpritzRng> {
+        let mut rng = Box::new(SpritzRng { sponge: Sponge::init() });
+        rng.sponge.key(seed, Some(nonce));
+        rng
+    }
+
+    /// A random byte.
+    pub fn random8(&mut self) -> u8 {
+        self.sponge.drip()
+    }
+
+    /// Four random bytes, the first one is the least significant.
+    pub fn random32(&mut self) -> u32 {
+        self.sponge.random32()
+    }
+
+    /// A uniformly distributed random number less than `upper_bound`, without modulo bias.
+    ///
+    /// Derives from OpenBSD's arc4random_uniform(), returns 0 if `upper_bound < 2`.
+    pub fn random32_uniform(&mut self, upper_bound: u32) -> u32 {
+        self.sponge.random32_uniform(upper_bound)
+    }
+
+    /// Fill `dest` with random bytes.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.sponge.fill_keystream(dest);
+    }
+
+    /// Mix `entropy` into the state.
+    pub fn add_entropy(&mut self, entropy: &[u8]) {
+        self.sponge.absorb_bytes(entropy);
+    }
+}
@@ -0,0 +1,163 @@
+//! Values kept encrypted while they sit in memory (`std`).
+
+use crate::{SpritzCipher, SpritzHasher};
+#[cfg(target_os = "linux")]
+use crate::mem::SecureBox;
+
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU64, Ordering};
+use std::boxed::Box;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+use zeroize::{Zeroize, Zeroizing};
+
+const KEY_LEN: usize = 32;
+
+/// Where the process key lives, locked memory when we can get it.
+enum ProcessKey {
+    #[cfg(target_os = "linux")]
+    Locked(SecureBox<[u8; KEY_LEN]>),
+    Heap(Box<[u8; KEY_LEN]>),
+}
+
+impl ProcessKey {
+    fn bytes(&self) -> &[u8; KEY_LEN] {
+        match self {
+            #[cfg(target_os = "linux")]
+            ProcessKey::Locked(key) => key,
+            ProcessKey::Heap(key) => key,
+        }
+    }
+}
+
+static PROCESS_KEY: OnceLock<ProcessKey> = OnceLock::new();
+/// Nonces are a counter, so one is never used twice under the process key.
+static NEXT_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// The ephemeral key all secrets are sealed under, made on first use.
+fn process_key() -> &'static [u8; KEY_LEN] {
+    PROCESS_KEY
+        .get_or_init(|| {
+            #[cfg(target_os = "linux")]
+            let mut key = match SecureBox::new([0u8; KEY_LEN]) {
+                Ok(key) => ProcessKey::Locked(key),
+                Err(_) => ProcessKey::Heap(Box::new([0u8; KEY_LEN])),
+            };
+            #[cfg(not(target_os = "linux"))]
+            let mut key = ProcessKey::Heap(Box::new([0u8; KEY_LEN]));
+
+            let bytes = match &mut key {
+                #[cfg(target_os = "linux")]
+                ProcessKey::Locked(key) => &mut **key,
+                ProcessKey::Heap(key) => &mut **key,
+            };
+            seed(bytes);
+            key
+        })
+        .bytes()
+}
+
+/// Fill `key` from the OS, through the hash so nothing else sees the raw entropy.
+fn seed(key: &mut [u8; KEY_LEN]) {
+    let mut slot = MaybeUninit::uninit();
+    let mut hasher = SpritzHasher::new_in(&mut slot);
+
+    /* std keys each thread's RandomState from the OS random source */
+    for k in 0u8..4 {
+        hasher.update(&RandomState::new().hash_one(k).to_le_bytes());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let mut entropy = Zeroizing::new([0u8; KEY_LEN]);
+        let read = unsafe { libc::getrandom(entropy.as_mut_ptr() as *mut libc::c_void, KEY_LEN, 0) };
+        if read == KEY_LEN as isize {
+            hasher.update(&*entropy);
+        }
+    }
+    if let Ok(now) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.update(&now.as_nanos().to_le_bytes());
+    }
+    hasher.update(&std::process::id().to_le_bytes());
+
+    /* A 32 byte output never exceeds the maximum digest size */
+    let _ = hasher.finalize(key);
+}
+
+/// XOR `bytes` with the keystream for `nonce` under the process key.
+fn apply(bytes: &mut [u8], nonce: u64) {
+    let mut slot = MaybeUninit::uninit();
+    SpritzCipher::with_nonce_in(&mut slot, process_key(), &nonce.to_le_bytes()).apply_keystream(bytes);
+}
+
+/// A value that is only in plain text while a closure is looking at it.
+///
+/// The bytes are encrypted with Spritz under a key made for this process, with a fresh nonce
+/// each time they are sealed. A heap dump shows only the ciphertext, and on Linux the key
+/// is kept in a [`SecureBox`](crate::mem::SecureBox), so it is missing from core dumps too.
+/// Anything that can read the live process can still decrypt it, this keeps secrets out of
+/// dumps and swap, it is not isolation.
+///
+/// ```
+/// use spritz_cipher::Secret;
+///
+/// let token = Secret::new(b"api-token".to_vec());
+/// let len = token.expose(|token| token.len());
+/// assert_eq!(len, 9);
+/// ```
+pub struct Secret<T: AsMut<[u8]> + Zeroize> {
+    sealed: T,
+    nonce: u64,
+}
+
+impl<T: AsMut<[u8]> + Zeroize> Secret<T> {
+    /// Encrypt `value` where it is, a `Vec` keeps its buffer so no plain text is left on the heap.
+    pub fn new(mut value: T) -> Secret<T> {
+        let nonce = NEXT_NONCE.fetch_add(1, Ordering::Relaxed);
+        apply(value.as_mut(), nonce);
+        Secret { sealed: value, nonce }
+    }
+
+    /// Call `f` with a decrypted copy of the value, which is wiped afterwards, even on a panic.
+    pub fn expose<R>(&self, f: impl FnOnce(&T) -> R) -> R
+    where
+        T: Clone,
+    {
+        let mut plain = Zeroizing::new(self.sealed.clone());
+        apply((*plain).as_mut(), self.nonce);
+        f(&plain)
+    }
+
+    /// Call `f` with the value decrypted in place, then seal it again under a new nonce.
+    ///
+    /// Growing a `Vec` here can reallocate it, which leaves the old buffer unwiped.
+    pub fn expose_mut<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        apply(self.sealed.as_mut(), self.nonce);
+        let guard = Reseal(self);
+        f(&mut guard.0.sealed)
+    }
+}
+
+/// Seals a [`Secret`] opened by `expose_mut`, when it drops so a panic can't skip it.
+struct Reseal<'a, T: AsMut<[u8]> + Zeroize>(&'a mut Secret<T>);
+
+impl<T: AsMut<[u8]> + Zeroize> Drop for Reseal<'_, T> {
+    fn drop(&mut self) {
+        self.0.nonce = NEXT_NONCE.fetch_add(1, Ordering::Relaxed);
+        apply(self.0.sealed.as_mut(), self.0.nonce);
+    }
+}
+
+impl<T: AsMut<[u8]> + Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.sealed.zeroize();
+    }
+}
+
+impl<T: AsMut<[u8]> + Zeroize> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
@@ -0,0 +1,702 @@
+//! The Spritz sponge that every context type is built on.
+
+use crate::{LengthEncoding, SpritzCipherError, MAX_DIGEST_SIZE};
+
+use core::hint::black_box;
+use zeroize::Zeroize;
+
+#[cfg(all(feature = "avx2", target_arch = "x86_64", not(feature = "hardened")))]
+mod avx2;
+
+const SPRITZ_N: usize = 256;
+const SPRITZ_N_MINUS_1: usize = SPRITZ_N - 1;
+const SPRITZ_N_HALF: usize = SPRITZ_N / 2;
+/// The length of a saved state, the S-box and the six registers.
+pub(crate) const STATE_LEN: usize = SPRITZ_N + 6;
+
+/// The Spritz state, zeroized on drop.
+#[derive(Zeroize)]
+#[zeroize(drop)]
+pub(crate) struct Sponge {
+    //s-box
+    s: [u8; SPRITZ_N],
+    i: u8,
+    j: u8,
+    k: u8,
+    z: u8,
+    a: u8,
+    w: u8,
+    
+    tmp1: u8,
+    tmp2: u8,
+
+}
+
+impl Sponge {
+    pub(crate) fn init() -> Sponge {
+
+        let mut context = Sponge {
+            //s-box
+            s: [0;SPRITZ_N],
+            i: 0,
+            j: 0,
+            k: 0,
+            z: 0,
+            a: 0,
+            w: 1,
+
+            tmp1: 0,
+            tmp2: 0,
+
+        };
+
+        for (key,val) in context.s.iter_mut().enumerate(){
+            *val = key as u8;
+        }
+
+        context
+    }
+
+    /// All ones if `a == b`, otherwise zero, without branching on either value.
+    #[cfg(feature = "hardened")]
+    #[inline(always)]
+    fn ct_eq_mask(a: u8, b: u8) -> u8 {
+        /* (a ^ b) - 1 only borrows into the high byte when a == b */
+        (((a ^ b) as u16).wrapping_sub(1) >> 8) as u8
+    }
+
+    /// Read `s[index]` where the index may depend on the key.
+    #[cfg(not(feature = "hardened"))]
+    #[inline(always)]
+    fn table_read(s: &[u8; SPRITZ_N], index: u8) -> u8 {
+        s[index as usize]
+    }
+
+    /// Read `s[index]` where the index may depend on the key.
+    ///
+    /// Hardened version, touches every entry so the memory access pattern is the same for all indices.
+    #[cfg(feature = "hardened")]
+    #[inline(always)]
+    fn table_read(s: &[u8; SPRITZ_N], index: u8) -> u8 {
+        let mut value = 0;
+        for (k, entry) in s.iter().enumerate() {
+            value |= entry & Sponge::ct_eq_mask(k as u8, index);
+        }
+        value
+    }
+
+    /// Set `s[index_a]` and `s[index_b]`, the values must be equal if the indices are.
+    #[cfg(not(feature = "hardened"))]
+    #[inline(always)]
+    fn table_write2(s: &mut [u8; SPRITZ_N], index_a: u8, value_a: u8, index_b: u8, value_b: u8) {
+        s[index_a as usize] = value_a;
+        s[index_b as usize] = value_b;
+    }
+
+    /// `update()` with the registers held outside a state, for keeping them in locals.
+    ///
+    /// Not in hardened builds, where the table scans over a borrowed S-box left a copy of it
+    /// on the stack, those go through `&mut self` instead.
+    #[cfg(not(feature = "hardened"))]
+    #[inline(always)]
+    fn step(s: &mut [u8; SPRITZ_N], i: &mut u8, j: &mut u8, k: &mut u8, w: u8) {
+        *i = i.wrapping_add(w);
+        let s_i = Sponge::table_read(s, *i);
+        *j = Sponge::table_read(s, s_i.wrapping_add(*j)).wrapping_add(*k);
+        let s_j = Sponge::table_read(s, *j);
+        *k = s_j.wrapping_add(*k).wrapping_add(*i);
+        /* Swap s[i] and s[j] with the values already read */
+        Sponge::table_write2(s, *i, s_j, *j, s_i);
+    }
+
+    /// `output()` with the registers held outside a state.
+    #[cfg(not(feature = "hardened"))]
+    #[inline(always)]
+    fn out(s: &[u8; SPRITZ_N], i: u8, j: u8, k: u8, z: &mut u8) -> u8 {
+        *z = Sponge::table_read(s, Sponge::table_read(s, Sponge::table_read(s, z.wrapping_add(k)).wrapping_add(i)).wrapping_add(j));
+        *z
+    }
+
+    /// Read `s[index]` where the index may depend on the key.
+    #[inline(always)]
+    fn s_read(&self, index: u8) -> u8 {
+        Sponge::table_read(&self.s, index)
+    }
+
+    #[cfg(not(feature = "hardened"))]
+    fn state_s_swap(&mut self, index_a: u8, index_b: u8)
+    {
+        self.tmp1       = self.s[index_a as usize];
+        self.s[index_a as usize] = self.s[index_b as usize];
+        self.s[index_b as usize] = self.tmp1;
+    }
+
+    /// Hardened version of the swap, every entry is rewritten whatever the indices are.
+    #[cfg(feature = "hardened")]
+    fn state_s_swap(&mut self, index_a: u8, index_b: u8)
+    {
+        self.tmp1 = self.s_read(index_a);
+        self.tmp2 = self.s_read(index_b);
+        for (k, entry) in self.s.iter_mut().enumerate() {
+            let mask_a = Sponge::ct_eq_mask(k as u8, index_a);
+            let mask_b = Sponge::ct_eq_mask(k as u8, index_b);
+            /* If index_a == index_b both masks are set and tmp1 == tmp2 */
+            *entry = (*entry & !(mask_a | mask_b)) | (self.tmp2 & mask_a) | (self.tmp1 & mask_b);
+        }
+    }
+
+    fn update(&mut self)
+    {
+        self.i = self.i.wrapping_add(self.w);
+        self.j = self.s_read(self.s_read(self.i).wrapping_add(self.j)).wrapping_add(self.k);
+        self.k = self.s_read(self.j).wrapping_add(self.k).wrapping_add(self.i);
+        self.state_s_swap(self.i, self.j);
+    }
+
+    fn whip(&mut self)
+    {
+        for _i in 0..SPRITZ_N_HALF {
+            self.update();
+            self.update();
+            self.update();
+            self.update();
+        }
+
+        /* w stays odd, so it stays relatively prime to N */
+        self.w = self.w.wrapping_add(2);
+    }
+
+    /// All ones if `a > b`, otherwise zero, without branching on either value.
+    #[inline(always)]
+    pub(crate) fn ct_gt_mask(a: u8, b: u8) -> u8 {
+        /* b - a borrows into the high byte exactly when a > b */
+        ((b as u16).wrapping_sub(a as u16) >> 8) as u8
+    }
+
+    /// Sorts each pair `s[i]`, `s[N - 1 - i]` so the smaller value comes first.
+    ///
+    /// The S-box is secret, so the swap is done with a mask instead of an `if`. The
+    /// mask goes through `black_box` so the optimiser can't turn it back into a branch.
+    #[inline(never)]
+    #[cold]
+    fn crush(&mut self){
+        let mut j = SPRITZ_N_MINUS_1;
+        for i in 0..SPRITZ_N_HALF {
+            self.tmp1 = self.s[i]; /* s_i=self.s[i] */
+            self.tmp2 = self.s[j]; /* s_j=self.s[j] */
+            /* d = (s_i ^ s_j) if s_i > s_j else 0 */
+            let d = (self.tmp1 ^ self.tmp2) & black_box(Sponge::ct_gt_mask(self.tmp1, self.tmp2));
+            self.s[i] = self.tmp1 ^ d; /* min(s_i, s_j) */
+            self.s[j] = self.tmp2 ^ d; /* max(s_i, s_j) */
+            j -= 1;
+        }
+    }
+
+    fn shuffle(&mut self)
+    {
+        self.whip();
+        self.crush();
+        self.whip();
+        self.crush();
+        self.whip();
+        self.a = 0;
+    }
+
+    fn absorb_nibble(&mut self, nibble: u8)
+    {
+        if self.a == SPRITZ_N_HALF as u8 {
+            self.shuffle();
+        }
+        self.state_s_swap(self.a, SPRITZ_N_HALF as u8 + nibble);
+        self.a += 1;
+    }
+
+    fn absorb(&mut self, octet: u8)
+    {
+        self.absorb_nibble(octet % 16); /* With the Right/Low nibble */
+        self.absorb_nibble(octet / 16); /* With the Left/High nibble */
+    }
+
+    pub(crate) fn absorb_bytes(&mut self, buf: &[u8])
+    {
+        //for byte in 0..buf.len {
+        for byte in buf.iter() {
+            self.absorb(*byte);
+        }
+    }
+
+    /// The digest length, see [`LengthEncoding`].
+    fn absorb_length(&mut self, len: usize)
+    {
+        if len <= MAX_DIGEST_SIZE {
+            self.absorb(len as u8);
+            return;
+        }
+
+        let bytes = (len as u64).to_le_bytes();
+        let count = bytes.len() - bytes.iter().rev().take_while(|&&b| b == 0).count();
+        self.absorb_bytes(&bytes[..count]);
+        self.absorb_stop();
+        self.absorb(count as u8);
+    }
+
+    pub(crate) fn absorb_stop(&mut self)
+    {
+        if self.a == SPRITZ_N_HALF as u8 {
+            self.shuffle();
+        }
+
+        self.a += 1;
+    }
+
+    fn output(&mut self) -> u8
+    {
+        self.z = self.s_read(
+                    self.s_read(
+                        self.s_read(
+                            self.z.wrapping_add(self.k)
+                        ).wrapping_add(self.i)
+                    ).wrapping_add(self.j)
+                );
+        self.z
+    }
+
+    pub(crate) fn drip(&mut self) -> u8
+    {
+        if self.a > 0 {
+            self.shuffle();
+        }
+        self.update();
+        self.output()
+    }
+
+    /// Run the keystream over `data`, calling `f` with each byte and its keystream byte.
+    ///
+    /// Gives the same bytes as calling [`drip`](Self::drip) for each one. The shuffle check is
+    /// done once up front (nothing in the loop sets `a`), the registers stay in locals, and the
+    /// S-box is a 256 entry array indexed by `u8`s, so there are no bounds checks either.
+    #[cfg(not(feature = "hardened"))]
+    #[inline(always)]
+    fn keystream_with(&mut self, data: &mut [u8], f: impl Fn(&mut u8, u8))
+    {
+        if self.a > 0 {
+            self.shuffle();
+        }
+
+        let s = &mut self.s;
+        let (mut i, mut j, mut k, mut z) = (self.i, self.j, self.k, self.z);
+        let w = self.w;
+        for byte in data.iter_mut() {
+            Sponge::step(s, &mut i, &mut j, &mut k, w);
+            f(byte, Sponge::out(s, i, j, k, &mut z));
+        }
+        self.i = i;
+        self.j = j;
+        self.k = k;
+        self.z = z;
+    }
+
+    /// Hardened version, every lookup has to scan the table so it stays a byte at a time.
+    #[cfg(feature = "hardened")]
+    #[inline(always)]
+    fn keystream_with(&mut self, data: &mut [u8], f: impl Fn(&mut u8, u8))
+    {
+        for byte in data.iter_mut() {
+            f(byte, self.drip());
+        }
+    }
+
+    /// XOR `data` with the keystream.
+    pub(crate) fn apply_keystream(&mut self, data: &mut [u8])
+    {
+        self.keystream_with(data, |byte, z| *byte ^= z);
+    }
+
+    /// Overwrite `out` with the keystream.
+    pub(crate) fn fill_keystream(&mut self, out: &mut [u8])
+    {
+        self.keystream_with(out, |byte, z| *byte = z);
+    }
+
+    /// Throw away the next `n` bytes of keystream.
+    pub(crate) fn skip_keystream(&mut self, mut n: usize)
+    {
+        let mut scratch = [0u8; 64];
+        while n > 0 {
+            let take = n.min(scratch.len());
+            self.fill_keystream(&mut scratch[..take]);
+            n -= take;
+        }
+        scratch.zeroize();
+    }
+
+    /// The state after absorbing `key` and, if given, a stop and `nonce`.
+    pub(crate) fn keyed(key: &[u8], nonce: Option<&[u8]>) -> Sponge
+    {
+        let mut sponge = Sponge::init();
+        sponge.key(key, nonce);
+        sponge
+    }
+
+    /// Absorb `key` and, if given, a stop and `nonce` into a fresh state, in place.
+    pub(crate) fn key(&mut self, key: &[u8], nonce: Option<&[u8]>)
+    {
+        self.absorb_bytes(key);
+        if let Some(nonce) = nonce {
+            self.absorb_stop();
+            self.absorb_bytes(nonce);
+        }
+        if self.a > 0 {
+            self.shuffle();
+        }
+    }
+
+    /// Absorb `nonce` into a state that has had a key and a stop, finishing the keying.
+    ///
+    /// After [`mac_key`](Self::mac_key) this gives the same state as [`key`](Self::key) with a
+    /// nonce, which is what lets a key schedule skip re-absorbing the key.
+    pub(crate) fn key_nonce(&mut self, nonce: &[u8])
+    {
+        self.absorb_bytes(nonce);
+        if self.a > 0 {
+            self.shuffle();
+        }
+    }
+
+    /// Overwrite this state with a copy of `other`, without a temporary on the stack.
+    pub(crate) fn copy_from(&mut self, other: &Sponge)
+    {
+        self.s = other.s;
+        self.i = other.i;
+        self.j = other.j;
+        self.k = other.k;
+        self.z = other.z;
+        self.a = other.a;
+        self.w = other.w;
+    }
+
+    /// Write the whole state out, for [`restore`](Self::restore).
+    pub(crate) fn save(&self, out: &mut [u8; STATE_LEN])
+    {
+        let (s, registers) = out.split_at_mut(SPRITZ_N);
+        s.copy_from_slice(&self.s);
+        registers.copy_from_slice(&[self.i, self.j, self.k, self.z, self.a, self.w]);
+    }
+
+    /// Overwrite this state with one written by [`save`](Self::save).
+    pub(crate) fn restore(&mut self, saved: &[u8; STATE_LEN])
+    {
+        let (s, registers) = saved.split_at(SPRITZ_N);
+        self.s.copy_from_slice(s);
+        if let [i, j, k, z, a, w] = *registers {
+            self.i = i;
+            self.j = j;
+            self.k = k;
+            self.z = z;
+            self.a = a;
+            self.w = w;
+        }
+    }
+
+    /// The state of a MAC, the key followed by a stop.
+    pub(crate) fn mac_keyed(key: &[u8]) -> Sponge
+    {
+        let mut sponge = Sponge::init();
+        sponge.mac_key(key);
+        sponge
+    }
+
+    /// Absorb a MAC key and a stop into a fresh state, in place.
+    pub(crate) fn mac_key(&mut self, key: &[u8])
+    {
+        self.absorb_bytes(key);
+        self.absorb_stop();
+    }
+
+    pub(crate) fn random32(&mut self) -> u32
+    {
+        (self.drip() as u32)
+        | ((self.drip() as u32) <<  8)
+        | ((self.drip() as u32) << 16)
+        | ((self.drip() as u32) << 24)
+    }
+
+    /// The smallest draw `random32_uniform` keeps, `2**32 % upper_bound`, so the draws it keeps
+    /// cover every residue equally often. `upper_bound` must be at least 2.
+    pub(crate) fn uniform_min(upper_bound: u32) -> u32
+    {
+        /* 2**32 % x == (2**32 - x) % x */
+        upper_bound.wrapping_neg() % upper_bound
+    }
+
+    pub(crate) fn random32_uniform(&mut self, upper_bound: u32) -> u32
+    {
+        if upper_bound < 2 {
+            return 0;
+        }
+
+        let min = Sponge::uniform_min(upper_bound);
+
+        /* This could theoretically loop forever but each retry has
+        * p > 0.5 (worst case, usually far better) of selecting a
+        * number inside the range we need, so it should rarely need
+        * to re-roll.
+        */
+        loop {
+            let r = self.random32();
+            if r >= min {
+                return r % upper_bound;
+            }
+        }
+    }
+
+    pub(crate) fn crypt(&mut self, data: &[u8], data_out: &mut [u8]) -> Result<(),SpritzCipherError>
+    {
+        if data.len() != data_out.len() {
+            return Err(SpritzCipherError::LengthsDontMatch { expected: data.len(), actual: data_out.len() });
+        }
+
+        data_out.copy_from_slice(data);
+        self.apply_keystream(data_out);
+        Ok(())
+    }
+
+    /// Absorb the digest length and squeeze out the digest, the state is left as it was on error.
+    pub(crate) fn squeeze(&mut self, digest: &mut [u8], encoding: LengthEncoding) -> Result<(),SpritzCipherError>
+    {
+        if encoding == LengthEncoding::Compat && digest.len() > MAX_DIGEST_SIZE {
+            return Err(SpritzCipherError::DigestTooLong { max: MAX_DIGEST_SIZE, actual: digest.len() });
+        }
+
+        self.squeeze_extended(digest);
+        Ok(())
+    }
+
+    /// Absorb the digest length with [`LengthEncoding::Extended`] and squeeze out the digest.
+    pub(crate) fn squeeze_extended(&mut self, digest: &mut [u8])
+    {
+        self.absorb_stop();
+        self.absorb_length(digest.len());
+        /* squeeze(), fill_keystream() does the shuffle */
+        self.fill_keystream(digest);
+    }
+}
+
+/* Independent states advanced in lockstep, one step of every lane at a time. Each state's
+ * steps form a chain of dependent table lookups, interleaving lanes lets the CPU overlap the
+ * chains. A lane only takes part in a step when its own state calls for it, so every lane
+ * ends up exactly where running it alone would have left it. */
+impl Sponge {
+    /// `shuffle` every lane in `active`.
+    #[cfg(not(feature = "hardened"))]
+    fn shuffle_lanes<const L: usize>(lanes: &mut [Sponge; L], active: [bool; L])
+    {
+        if !active.contains(&true) {
+            return;
+        }
+
+        /* whip, crush, whip, crush, whip */
+        for round in 0..3 {
+            if !Sponge::whip_avx2(lanes, active) {
+                let mut i: [u8; L] = core::array::from_fn(|l| lanes[l].i);
+                let mut j: [u8; L] = core::array::from_fn(|l| lanes[l].j);
+                let mut k: [u8; L] = core::array::from_fn(|l| lanes[l].k);
+                let w: [u8; L] = core::array::from_fn(|l| lanes[l].w);
+                for _ in 0..4 * SPRITZ_N_HALF {
+                    for l in 0..L {
+                        if active[l] {
+                            Sponge::step(&mut lanes[l].s, &mut i[l], &mut j[l], &mut k[l], w[l]);
+                        }
+                    }
+                }
+                for l in 0..L {
+                    lanes[l].i = i[l];
+                    lanes[l].j = j[l];
+                    lanes[l].k = k[l];
+                }
+            }
+            for (lane, &on) in lanes.iter_mut().zip(active.iter()) {
+                if on {
+                    lane.w = lane.w.wrapping_add(2);
+                    if round < 2 {
+                        lane.crush();
+                    } else {
+                        lane.a = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hardened version, each lane shuffles on its own so the S-box stays in its state.
+    #[cfg(feature = "hardened")]
+    fn shuffle_lanes<const L: usize>(lanes: &mut [Sponge; L], active: [bool; L])
+    {
+        for (lane, &on) in lanes.iter_mut().zip(active.iter()) {
+            if on {
+                lane.shuffle();
+            }
+        }
+    }
+
+    /// Absorb `inputs[l]` into lane `l`.
+    pub(crate) fn absorb_lanes<const L: usize>(lanes: &mut [Sponge; L], inputs: [&[u8]; L])
+    {
+        let longest = inputs.iter().map(|input| input.len()).max().unwrap_or(0);
+        for pos in 0..longest {
+            /* absorb_nibble(), low nibble first */
+            for high in [false, true] {
+                let full = core::array::from_fn(|l| pos < inputs[l].len() && lanes[l].a == SPRITZ_N_HALF as u8);
+                Sponge::shuffle_lanes(lanes, full);
+
+                for (lane, input) in lanes.iter_mut().zip(inputs.iter()) {
+                    if let Some(byte) = input.get(pos) {
+                        let nibble = if high { byte / 16 } else { byte % 16 };
+                        lane.state_s_swap(lane.a, SPRITZ_N_HALF as u8 + nibble);
+                        lane.a += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `absorb_stop` every lane in `active`.
+    pub(crate) fn stop_lanes<const L: usize>(lanes: &mut [Sponge; L], active: [bool; L])
+    {
+        let full = core::array::from_fn(|l| active[l] && lanes[l].a == SPRITZ_N_HALF as u8);
+        Sponge::shuffle_lanes(lanes, full);
+        for (lane, &on) in lanes.iter_mut().zip(active.iter()) {
+            if on {
+                lane.a += 1;
+            }
+        }
+    }
+
+    /// `key` every lane with its own key and, if given, nonce.
+    pub(crate) fn key_lanes<const L: usize>(lanes: &mut [Sponge; L], keys: [&[u8]; L], nonces: Option<[&[u8]; L]>)
+    {
+        Sponge::absorb_lanes(lanes, keys);
+        if let Some(nonces) = nonces {
+            Sponge::stop_lanes(lanes, [true; L]);
+            Sponge::absorb_lanes(lanes, nonces);
+        }
+        let pending = core::array::from_fn(|l| lanes[l].a > 0);
+        Sponge::shuffle_lanes(lanes, pending);
+    }
+
+    /// `absorb_length` every lane with its own length.
+    fn absorb_length_lanes<const L: usize>(lanes: &mut [Sponge; L], lens: [usize; L])
+    {
+        let bytes: [[u8; 8]; L] = core::array::from_fn(|l| (lens[l] as u64).to_le_bytes());
+        let counts: [usize; L] = core::array::from_fn(|l| 8 - bytes[l].iter().rev().take_while(|&&b| b == 0).count());
+        let long: [bool; L] = core::array::from_fn(|l| lens[l] > MAX_DIGEST_SIZE);
+        let count_bytes: [[u8; 1]; L] = core::array::from_fn(|l| [counts[l] as u8]);
+
+        /* A short length is its one byte, a long one its bytes, a stop and the byte count */
+        Sponge::absorb_lanes(lanes, core::array::from_fn(|l| if long[l] { &bytes[l][..counts[l]] } else { &bytes[l][..1] }));
+        Sponge::stop_lanes(lanes, long);
+        Sponge::absorb_lanes(lanes, core::array::from_fn(|l| if long[l] { &count_bytes[l][..] } else { &[][..] }));
+    }
+
+    /// `squeeze_extended` every lane into its own digest.
+    pub(crate) fn squeeze_lanes<const L: usize>(lanes: &mut [Sponge; L], digests: &mut [&mut [u8]; L])
+    {
+        Sponge::stop_lanes(lanes, [true; L]);
+        Sponge::absorb_length_lanes(lanes, core::array::from_fn(|l| digests[l].len()));
+        Sponge::keystream_lanes(lanes, digests, |byte, z| *byte = z);
+    }
+
+    /// XOR `data[l]` with lane `l`'s keystream.
+    pub(crate) fn apply_keystream_lanes<const L: usize>(lanes: &mut [Sponge; L], data: &mut [&mut [u8]; L])
+    {
+        Sponge::keystream_lanes(lanes, data, |byte, z| *byte ^= z);
+    }
+
+    /// `keystream_with` on every lane.
+    #[cfg(not(feature = "hardened"))]
+    #[inline(always)]
+    fn keystream_lanes<const L: usize>(lanes: &mut [Sponge; L], data: &mut [&mut [u8]; L], f: impl Fn(&mut u8, u8))
+    {
+        let pending = core::array::from_fn(|l| lanes[l].a > 0);
+        Sponge::shuffle_lanes(lanes, pending);
+
+        /* Lockstep while every lane has data, then each lane finishes alone */
+        let shortest = data.iter().map(|data| data.len()).min().unwrap_or(0);
+        if !Sponge::keystream_avx2(lanes, data, shortest, &f) {
+            let mut i: [u8; L] = core::array::from_fn(|l| lanes[l].i);
+            let mut j: [u8; L] = core::array::from_fn(|l| lanes[l].j);
+            let mut k: [u8; L] = core::array::from_fn(|l| lanes[l].k);
+            let mut z: [u8; L] = core::array::from_fn(|l| lanes[l].z);
+            let w: [u8; L] = core::array::from_fn(|l| lanes[l].w);
+            for pos in 0..shortest {
+                for (l, (lane, data)) in lanes.iter_mut().zip(data.iter_mut()).enumerate() {
+                    Sponge::step(&mut lane.s, &mut i[l], &mut j[l], &mut k[l], w[l]);
+                    f(&mut data[pos], Sponge::out(&lane.s, i[l], j[l], k[l], &mut z[l]));
+                }
+            }
+            for l in 0..L {
+                lanes[l].i = i[l];
+                lanes[l].j = j[l];
+                lanes[l].k = k[l];
+                lanes[l].z = z[l];
+            }
+        }
+        for (lane, data) in lanes.iter_mut().zip(data.iter_mut()) {
+            lane.keystream_with(&mut data[shortest..], &f);
+        }
+    }
+
+    /// The `whip` of `shuffle_lanes` eight lanes at a time, if the CPU has AVX2, every lane is
+    /// active and they split into vectors. Returns whether it ran.
+    #[cfg(all(feature = "avx2", target_arch = "x86_64", not(feature = "hardened")))]
+    fn whip_avx2<const L: usize>(lanes: &mut [Sponge; L], active: [bool; L]) -> bool
+    {
+        if !L.is_multiple_of(avx2::WIDTH) || active.contains(&false) || !avx2::available() {
+            return false;
+        }
+        for group in lanes.chunks_exact_mut(avx2::WIDTH) {
+            /* Safe, the CPU has AVX2 and the group is a whole vector */
+            unsafe { avx2::whip(group) };
+        }
+        true
+    }
+
+    /// The lockstep part of `keystream_lanes` eight lanes at a time, if the CPU has AVX2 and
+    /// the lanes split into vectors. Returns whether it ran.
+    #[cfg(all(feature = "avx2", target_arch = "x86_64", not(feature = "hardened")))]
+    fn keystream_avx2<const L: usize>(lanes: &mut [Sponge; L], data: &mut [&mut [u8]; L], len: usize, f: &impl Fn(&mut u8, u8)) -> bool
+    {
+        if !L.is_multiple_of(avx2::WIDTH) || !avx2::available() {
+            return false;
+        }
+        for (group, data) in lanes.chunks_exact_mut(avx2::WIDTH).zip(data.chunks_exact_mut(avx2::WIDTH)) {
+            /* Safe, the CPU has AVX2, the group is a whole vector and no data is shorter than `len` */
+            unsafe { avx2::keystream(group, data, len, f) };
+        }
+        true
+    }
+
+    /// Without the `avx2` feature the portable loops always run.
+    #[cfg(all(not(all(feature = "avx2", target_arch = "x86_64")), not(feature = "hardened")))]
+    fn whip_avx2<const L: usize>(_lanes: &mut [Sponge; L], _active: [bool; L]) -> bool
+    {
+        false
+    }
+
+    /// Without the `avx2` feature the portable loops always run.
+    #[cfg(all(not(all(feature = "avx2", target_arch = "x86_64")), not(feature = "hardened")))]
+    fn keystream_avx2<const L: usize>(_lanes: &mut [Sponge; L], _data: &mut [&mut [u8]; L], _len: usize, _f: &impl Fn(&mut u8, u8)) -> bool
+    {
+        false
+    }
+
+    /// Hardened version, one lane after the other.
+    #[cfg(feature = "hardened")]
+    #[inline(always)]
+    fn keystream_lanes<const L: usize>(lanes: &mut [Sponge; L], data: &mut [&mut [u8]; L], f: impl Fn(&mut u8, u8))
+    {
+        for (lane, data) in lanes.iter_mut().zip(data.iter_mut()) {
+            lane.keystream_with(data, &f);
+        }
+    }
+}
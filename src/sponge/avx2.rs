@@ -0,0 +1,125 @@
+//! The lockstep loops eight lanes at a time with AVX2, the `avx2` feature on x86-64. They
+//! only run when the CPU has AVX2, otherwise the portable loops do.
+//!
+//! The eight S-boxes are interleaved into one table, entry `n` of lane `l` at `8 * n + l` and
+//! widened to 32 bits, so one gather looks an entry up in all eight lanes. AVX2 has no
+//! scatter, the two swap writes of each lane are still scalar stores.
+
+use super::{Sponge, SPRITZ_N, SPRITZ_N_HALF};
+
+use core::arch::x86_64::*;
+use zeroize::Zeroize;
+
+/// Lanes in a vector.
+pub(super) const WIDTH: usize = 8;
+
+/// Whether this CPU can run the AVX2 loops.
+pub(super) fn available() -> bool {
+    std::is_x86_feature_detected!("avx2")
+}
+
+/// `whip` every lane of `lanes`, without the `w` update.
+///
+/// # Safety
+/// The CPU must have AVX2, see [`available`]. `lanes` holds [`WIDTH`] lanes.
+pub(super) unsafe fn whip(lanes: &mut [Sponge]) {
+    run::<false>(lanes, 4 * SPRITZ_N_HALF, |_, _| {});
+}
+
+/// The keystream of every lane for `len` bytes, calling `f` with `data[l][pos]` and its byte.
+///
+/// # Safety
+/// The CPU must have AVX2, see [`available`]. `lanes` and `data` hold [`WIDTH`] lanes and every
+/// `data[l]` is at least `len` long.
+pub(super) unsafe fn keystream(lanes: &mut [Sponge], data: &mut [&mut [u8]], len: usize, f: &impl Fn(&mut u8, u8)) {
+    run::<true>(lanes, len, |pos, z| {
+        for (data, &z) in data.iter_mut().zip(z.iter()) {
+            f(&mut data[pos], z as u8);
+        }
+    });
+}
+
+#[inline(always)]
+unsafe fn load(values: &[u32; WIDTH]) -> __m256i {
+    _mm256_loadu_si256(values.as_ptr() as *const __m256i)
+}
+
+#[inline(always)]
+unsafe fn store(vector: __m256i, values: &mut [u32; WIDTH]) {
+    _mm256_storeu_si256(values.as_mut_ptr() as *mut __m256i, vector);
+}
+
+/// `steps` updates of every lane, and an output after each one if `OUT`.
+#[target_feature(enable = "avx2")]
+unsafe fn run<const OUT: bool>(lanes: &mut [Sponge], steps: usize, mut emit: impl FnMut(usize, &[u32; WIDTH])) {
+    let mut table = [0u32; SPRITZ_N * WIDTH];
+    for (l, lane) in lanes.iter().enumerate() {
+        for (n, &entry) in lane.s.iter().enumerate() {
+            table[n * WIDTH + l] = entry as u32;
+        }
+    }
+    let ptr = table.as_mut_ptr();
+
+    let register = |get: fn(&Sponge) -> u8| -> [u32; WIDTH] { core::array::from_fn(|l| get(&lanes[l]) as u32) };
+    let mut i = load(&register(|lane| lane.i));
+    let mut j = load(&register(|lane| lane.j));
+    let mut k = load(&register(|lane| lane.k));
+    let mut z = load(&register(|lane| lane.z));
+    let w = load(&register(|lane| lane.w));
+
+    let ids = _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7);
+    let byte = _mm256_set1_epi32(0xff);
+    /* Entry `n mod 256` of every lane */
+    let lookup = |n: __m256i| {
+        let index = _mm256_or_si256(_mm256_slli_epi32::<3>(_mm256_and_si256(n, byte)), ids);
+        _mm256_i32gather_epi32::<4>(ptr as *const i32, index)
+    };
+
+    let (mut at_i, mut at_j, mut new_i, mut new_j, mut out) = ([0u32; WIDTH], [0u32; WIDTH], [0u32; WIDTH], [0u32; WIDTH], [0u32; WIDTH]);
+    for pos in 0..steps {
+        /* update() */
+        i = _mm256_and_si256(_mm256_add_epi32(i, w), byte);
+        let s_i = lookup(i);
+        j = _mm256_and_si256(_mm256_add_epi32(lookup(_mm256_add_epi32(s_i, j)), k), byte);
+        let s_j = lookup(j);
+        k = _mm256_and_si256(_mm256_add_epi32(_mm256_add_epi32(s_j, k), i), byte);
+
+        /* Swap s[i] and s[j] with the values already read, in the same order as `step` */
+        store(i, &mut at_i);
+        store(j, &mut at_j);
+        store(s_j, &mut new_i);
+        store(s_i, &mut new_j);
+        for l in 0..WIDTH {
+            *ptr.add(at_i[l] as usize * WIDTH + l) = new_i[l];
+            *ptr.add(at_j[l] as usize * WIDTH + l) = new_j[l];
+        }
+
+        if OUT {
+            /* output() */
+            z = lookup(_mm256_add_epi32(lookup(_mm256_add_epi32(lookup(_mm256_add_epi32(z, k)), i)), j));
+            store(z, &mut out);
+            emit(pos, &out);
+        }
+    }
+
+    let mut registers = [[0u32; WIDTH]; 4];
+    for (vector, values) in [i, j, k, z].iter().zip(registers.iter_mut()) {
+        store(*vector, values);
+    }
+    for (l, lane) in lanes.iter_mut().enumerate() {
+        for (n, entry) in lane.s.iter_mut().enumerate() {
+            *entry = table[n * WIDTH + l] as u8;
+        }
+        lane.i = registers[0][l] as u8;
+        lane.j = registers[1][l] as u8;
+        lane.k = registers[2][l] as u8;
+        lane.z = registers[3][l] as u8;
+    }
+
+    /* The table and the scratch arrays hold the S-boxes and registers */
+    table.zeroize();
+    registers.zeroize();
+    for scratch in [&mut at_i, &mut at_j, &mut new_i, &mut new_j, &mut out] {
+        scratch.zeroize();
+    }
+}
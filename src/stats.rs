@@ -0,0 +1,375 @@
+//! Statistical tests for the Spritz keystream.
+//!
+//! A small battery in the style of NIST SP 800-22 (and the FIPS 140-1 poker test).
+//! Every test looks at the input as a bit string, most significant bit of each byte first,
+//! and reports a p-value. A test passes when its p-value is at least the chosen
+//! significance level, usually [`DEFAULT_ALPHA`].
+//!
+//! Only available with the `std` feature.
+//!
+//! ```
+//! use spritz_cipher::SpritzRng;
+//! use spritz_cipher::stats;
+//!
+//! let mut rng = SpritzRng::new(b"key");
+//! let sample = stats::keystream(&mut rng, stats::DEFAULT_SAMPLE_SIZE);
+//!
+//! for result in stats::run_all(&sample) {
+//!     assert!(result.passed(stats::DEFAULT_ALPHA), "{}", result);
+//! }
+//! ```
+
+use crate::SpritzRng;
+
+use core::fmt;
+use std::vec::Vec;
+
+/// Significance level used by NIST SP 800-22.
+pub const DEFAULT_ALPHA: f64 = 0.01;
+
+/// Default sample size in bytes (10^6 bits, as recommended by NIST SP 800-22).
+pub const DEFAULT_SAMPLE_SIZE: usize = 125_000;
+
+/// Block length in bits for the serial test.
+pub const SERIAL_BLOCK_BITS: u32 = 8;
+
+/// Shift in bits for the autocorrelation test.
+pub const AUTOCORRELATION_SHIFT: usize = 1;
+
+/// The outcome of a single statistical test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    /// Name of the test.
+    pub name: &'static str,
+    /// The test statistic (chi-square value, normalised deviation, ...).
+    pub statistic: f64,
+    /// The p-value, the probability of a truly random source doing at least this badly.
+    pub p_value: f64,
+}
+
+impl TestResult {
+    /// True if the p-value is at least `alpha`.
+    pub fn passed(&self, alpha: f64) -> bool {
+        self.p_value >= alpha
+    }
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<16} statistic = {:>12.6}  p-value = {:.6}", self.name, self.statistic, self.p_value)
+    }
+}
+
+/// Online Welch's t-test between two classes of measurements.
+///
+/// Used by the timing leakage harness (`examples/timing_leak.rs`) in the style of dudect:
+/// class 0 gets measurements for a fixed input, class 1 for random inputs. A large `|t|`
+/// means the two classes have different means, i.e. the timing depends on the input.
+#[derive(Debug, Clone, Default)]
+pub struct WelchTTest {
+    count: [f64; 2],
+    mean: [f64; 2],
+    m2: [f64; 2],
+}
+
+impl WelchTTest {
+    /// An empty test.
+    pub fn new() -> WelchTTest {
+        WelchTTest::default()
+    }
+
+    /// Add a measurement to `class` (0 or 1, anything else counts as 1).
+    pub fn push(&mut self, class: usize, value: f64) {
+        let c = class.min(1);
+        /* Welford's online mean and variance */
+        self.count[c] += 1.0;
+        let delta = value - self.mean[c];
+        self.mean[c] += delta / self.count[c];
+        self.m2[c] += delta * (value - self.mean[c]);
+    }
+
+    /// Number of measurements in each class.
+    pub fn counts(&self) -> [u64; 2] {
+        [self.count[0] as u64, self.count[1] as u64]
+    }
+
+    /// Mean of each class.
+    pub fn means(&self) -> [f64; 2] {
+        self.mean
+    }
+
+    /// The t statistic, 0 until both classes have at least two measurements.
+    pub fn t(&self) -> f64 {
+        if self.count[0] < 2.0 || self.count[1] < 2.0 {
+            return 0.0;
+        }
+
+        let var0 = self.m2[0] / (self.count[0] - 1.0);
+        let var1 = self.m2[1] / (self.count[1] - 1.0);
+        let denominator = (var0 / self.count[0] + var1 / self.count[1]).sqrt();
+        if denominator == 0.0 {
+            return 0.0;
+        }
+        (self.mean[0] - self.mean[1]) / denominator
+    }
+}
+
+/// Collect `len` bytes of raw keystream, the same bytes [`SpritzCipher`](crate::SpritzCipher)
+/// XORs with the data for the same key.
+pub fn keystream(rng: &mut SpritzRng, len: usize) -> Vec<u8> {
+    let mut out = std::vec![0u8; len];
+    rng.fill_bytes(&mut out);
+    out
+}
+
+/// Collect `words` values of [`SpritzRng::random32`] as little endian bytes.
+pub fn random32_stream(rng: &mut SpritzRng, words: usize) -> Vec<u8> {
+    /* Only a hint, a count too large to allocate grows as it goes until memory runs out */
+    let mut out = Vec::new();
+    let _ = out.try_reserve_exact(words.saturating_mul(4));
+    for _ in 0..words {
+        out.extend_from_slice(&rng.random32().to_le_bytes());
+    }
+    out
+}
+
+/// Run every test in the battery with its default parameters.
+pub fn run_all(data: &[u8]) -> Vec<TestResult> {
+    let mut results = Vec::new();
+    results.push(monobit(data));
+    results.push(runs(data));
+    results.push(byte_frequency(data));
+    results.extend_from_slice(&serial(data, SERIAL_BLOCK_BITS));
+    results.push(autocorrelation(data, AUTOCORRELATION_SHIFT));
+    results.push(poker(data));
+    results
+}
+
+fn bit(data: &[u8], index: usize) -> u8 {
+    (data[index / 8] >> (7 - index % 8)) & 1
+}
+
+fn ones(data: &[u8]) -> u64 {
+    data.iter().map(|b| b.count_ones() as u64).sum()
+}
+
+/// Frequency (monobit) test, NIST SP 800-22 section 2.1.
+pub fn monobit(data: &[u8]) -> TestResult {
+    let n = data.len() as f64 * 8.0;
+    let sum = 2.0 * ones(data) as f64 - n;
+    let statistic = sum.abs() / n.sqrt();
+
+    TestResult {
+        name: "monobit",
+        statistic,
+        p_value: erfc(statistic / core::f64::consts::SQRT_2),
+    }
+}
+
+/// Runs test, NIST SP 800-22 section 2.3.
+pub fn runs(data: &[u8]) -> TestResult {
+    let bits = data.len() * 8;
+    let n = bits as f64;
+    let pi = ones(data) as f64 / n;
+
+    /* The frequency pre-test, without it the runs test isn't meaningful */
+    if bits == 0 || (pi - 0.5).abs() >= 2.0 / n.sqrt() {
+        return TestResult { name: "runs", statistic: 0.0, p_value: 0.0 };
+    }
+
+    let runs = 1 + (1..bits).filter(|&k| bit(data, k) != bit(data, k - 1)).count();
+    let expected = 2.0 * n * pi * (1.0 - pi);
+    let statistic = runs as f64;
+
+    TestResult {
+        name: "runs",
+        statistic,
+        p_value: erfc((statistic - expected).abs() / (2.0 * (2.0 * n).sqrt() * pi * (1.0 - pi))),
+    }
+}
+
+/// Chi-square goodness of fit of the byte values against the uniform distribution.
+pub fn byte_frequency(data: &[u8]) -> TestResult {
+    let mut counts = [0u64; 256];
+    for byte in data.iter() {
+        counts[*byte as usize] += 1;
+    }
+
+    let statistic = chi_square(&counts, data.len() as f64 / 256.0);
+
+    TestResult {
+        name: "byte frequency",
+        statistic,
+        p_value: igamc(255.0 / 2.0, statistic / 2.0),
+    }
+}
+
+fn psi_squared(data: &[u8], m: u32) -> f64 {
+    let bits = data.len() * 8;
+    if m == 0 || bits == 0 {
+        return 0.0;
+    }
+
+    let mask = (1usize << m) - 1;
+    let mut counts = std::vec![0u64; 1 << m];
+
+    /* Overlapping m-bit patterns, wrapping around to the start */
+    let mut pattern = 0;
+    for k in 0..(m as usize - 1) {
+        pattern = (pattern << 1) | bit(data, k % bits) as usize;
+    }
+    for k in 0..bits {
+        pattern = ((pattern << 1) | bit(data, (k + m as usize - 1) % bits) as usize) & mask;
+        counts[pattern] += 1;
+    }
+
+    let n = bits as f64;
+    let sum: f64 = counts.iter().map(|&c| (c as f64) * (c as f64)).sum();
+    sum * (1u64 << m) as f64 / n - n
+}
+
+/// Serial test, NIST SP 800-22 section 2.11.
+///
+/// Checks the overlapping `m`-bit patterns (for `m == 8` these are the digraphs across byte
+/// boundaries) and returns the two p-values of the test. `m` is clamped to `3..=16`.
+pub fn serial(data: &[u8], m: u32) -> [TestResult; 2] {
+    let m = m.clamp(3, 16);
+    let psi_m = psi_squared(data, m);
+    let psi_m1 = psi_squared(data, m - 1);
+    let psi_m2 = psi_squared(data, m - 2);
+
+    let delta1 = psi_m - psi_m1;
+    let delta2 = psi_m - 2.0 * psi_m1 + psi_m2;
+
+    [
+        TestResult {
+            name: "serial 1",
+            statistic: delta1,
+            p_value: igamc((1u64 << (m - 2)) as f64, delta1 / 2.0),
+        },
+        TestResult {
+            name: "serial 2",
+            statistic: delta2,
+            p_value: igamc((1u64 << (m - 3)) as f64, delta2 / 2.0),
+        },
+    ]
+}
+
+/// Autocorrelation test, compares the bit string with itself shifted by `shift` bits.
+pub fn autocorrelation(data: &[u8], shift: usize) -> TestResult {
+    let bits = data.len() * 8;
+    let n = bits.saturating_sub(shift) as f64;
+    let differing = (0..bits.saturating_sub(shift)).filter(|&k| bit(data, k) != bit(data, k + shift)).count();
+    let statistic = 2.0 * (differing as f64 - n / 2.0) / n.sqrt();
+
+    TestResult {
+        name: "autocorrelation",
+        statistic,
+        p_value: erfc(statistic.abs() / core::f64::consts::SQRT_2),
+    }
+}
+
+/// Poker test (FIPS 140-1) over the 4-bit nibbles.
+pub fn poker(data: &[u8]) -> TestResult {
+    let mut counts = [0u64; 16];
+    for byte in data.iter() {
+        counts[(byte >> 4) as usize] += 1;
+        counts[(byte & 0x0f) as usize] += 1;
+    }
+
+    let statistic = chi_square(&counts, data.len() as f64 * 2.0 / 16.0);
+
+    TestResult {
+        name: "poker",
+        statistic,
+        p_value: igamc(15.0 / 2.0, statistic / 2.0),
+    }
+}
+
+fn chi_square(counts: &[u64], expected: f64) -> f64 {
+    counts.iter().map(|&c| (c as f64 - expected) * (c as f64 - expected) / expected).sum()
+}
+
+/// Complementary error function.
+pub fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        2.0 - igamc(0.5, x * x)
+    } else {
+        igamc(0.5, x * x)
+    }
+}
+
+/// Natural log of the gamma function (Lanczos approximation).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.120_865_097_386_617_9e-2,
+        -0.539_523_938_495_3e-5,
+    ];
+
+    let mut y = x;
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000_000_000_190_015;
+    for c in COEFFICIENTS.iter() {
+        y += 1.0;
+        series += c / y;
+    }
+
+    -tmp + (2.506_628_274_631_000_5 * series / x).ln()
+}
+
+/// Regularized upper incomplete gamma function Q(a, x).
+pub fn igamc(a: f64, x: f64) -> f64 {
+    const ITERATIONS: usize = 1000;
+    const EPSILON: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+
+    if x <= 0.0 || a <= 0.0 {
+        return 1.0;
+    }
+
+    if x < a + 1.0 {
+        /* Series for P(a, x) */
+        let mut ap = a;
+        let mut del = 1.0 / a;
+        let mut sum = del;
+        for _ in 0..ITERATIONS {
+            ap += 1.0;
+            del *= x / ap;
+            sum += del;
+            if del.abs() < sum.abs() * EPSILON {
+                break;
+            }
+        }
+        1.0 - sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+    } else {
+        /* Continued fraction for Q(a, x), modified Lentz's method */
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let del = d * c;
+            h *= del;
+            if (del - 1.0).abs() < EPSILON {
+                break;
+            }
+        }
+        (-x + a * x.ln() - ln_gamma(a)).exp() * h
+    }
+}
@@ -0,0 +1,348 @@
+//! Authenticated encryption of long streams in chunks, the STREAM construction.
+//!
+//! A large file can't be authenticated before any of it is used, but each chunk of it can.
+//! Every chunk is encrypted and tagged on its own, with a nonce made of the stream's nonce,
+//! the chunk's number and a flag for the last chunk. The decryptor only hands out chunks whose
+//! tag checks, and the number and flag mean chunks that are reordered, repeated or dropped
+//! fail to authenticate, as does a stream that is cut short or added to after its last chunk.
+//!
+//! Chunk `n` is keyed like a [`SpritzCtr`](crate::SpritzCtr) block: the key, a stop, the
+//! nonce, a stop, then `n` as 8 little-endian bytes and the flag byte. The first 32 bytes of
+//! its keystream are a one time MAC key and the rest encrypts the chunk, the tag is the
+//! [`SpritzMac`] of the ciphertext under that key. Tagging absorbs every byte, so this runs at
+//! the speed of the MAC, not of the keystream. Don't use the same key and nonce with `SpritzCtr`.
+//!
+//! [`StreamEncryptor`] and [`StreamDecryptor`] work on chunks of any length in the caller's
+//! buffers. With `std`, [`StreamWriter`] and [`StreamReader`] wrap `io::Write` and `io::Read`,
+//! storing each [`CHUNK_LEN`] byte chunk followed by its tag.
+//!
+//! ```
+//! use spritz_cipher::stream::{StreamDecryptor, StreamEncryptor};
+//!
+//! let mut chunks = [*b"first chunk", *b"last chunk!"];
+//! let mut encryptor = StreamEncryptor::new(b"key", b"nonce");
+//! let first = encryptor.encrypt_next(&mut chunks[0]).unwrap();
+//! let last = encryptor.encrypt_last(&mut chunks[1]);
+//!
+//! let mut decryptor = StreamDecryptor::new(b"key", b"nonce");
+//! decryptor.decrypt_next(&mut chunks[0], &first).unwrap();
+//! decryptor.decrypt_last(&mut chunks[1], &last).unwrap();
+//! assert_eq!(&chunks[1], b"last chunk!");
+//! ```
+
+use crate::sponge::Sponge;
+use crate::{SpritzCipherError, SpritzMac, Tag};
+
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::vec::Vec;
+use zeroize::Zeroizing;
+
+/// The length of a chunk's tag.
+pub const TAG_LEN: usize = 32;
+
+/// The plain text length of every chunk but the last one, for [`StreamWriter`] and
+/// [`StreamReader`].
+pub const CHUNK_LEN: usize = 1 << 16;
+
+/// A stored chunk and its tag.
+#[cfg(feature = "std")]
+const FRAME_LEN: usize = CHUNK_LEN + TAG_LEN;
+
+/// The key, a stop, the nonce and a stop.
+fn keyed(key: &[u8], nonce: &[u8]) -> Sponge {
+    let mut base = Sponge::mac_keyed(key);
+    base.absorb_bytes(nonce);
+    base.absorb_stop();
+    base
+}
+
+/// The state chunk `counter` is encrypted with, and its MAC key.
+fn chunk_cipher(base: &Sponge, counter: u64, last: bool) -> (Sponge, Zeroizing<[u8; TAG_LEN]>) {
+    let mut sponge = Sponge::init();
+    sponge.copy_from(base);
+    sponge.absorb_bytes(&counter.to_le_bytes());
+    sponge.absorb_bytes(&[last as u8]);
+
+    let mut mac_key = Zeroizing::new([0u8; TAG_LEN]);
+    sponge.fill_keystream(&mut *mac_key);
+    (sponge, mac_key)
+}
+
+fn seal(base: &Sponge, counter: u64, last: bool, chunk: &mut [u8]) -> Tag<TAG_LEN> {
+    let (mut sponge, mac_key) = chunk_cipher(base, counter, last);
+    sponge.apply_keystream(chunk);
+    chunk_tag(&mac_key, chunk)
+}
+
+/// Check the tag, then decrypt, `chunk` is left alone if the tag is wrong.
+fn open(base: &Sponge, counter: u64, last: bool, chunk: &mut [u8], tag: &Tag<TAG_LEN>) -> Result<(), SpritzCipherError> {
+    let (mut sponge, mac_key) = chunk_cipher(base, counter, last);
+    if chunk_tag(&mac_key, chunk) != *tag {
+        return Err(SpritzCipherError::AuthenticationFailed);
+    }
+    sponge.apply_keystream(chunk);
+    Ok(())
+}
+
+/// The tag of `msg` under the chunk's MAC key.
+fn chunk_tag(mac_key: &[u8; TAG_LEN], msg: &[u8]) -> Tag<TAG_LEN> {
+    let mut mac = SpritzMac::new(mac_key);
+    mac.update(msg);
+    mac.finalize_tag()
+}
+
+/// Encrypts a stream a chunk at a time.
+pub struct StreamEncryptor {
+    base: Sponge,
+    counter: u64,
+}
+
+impl StreamEncryptor {
+    /// Start a stream, never reuse a nonce with the same key.
+    pub fn new(key: &[u8], nonce: &[u8]) -> StreamEncryptor {
+        StreamEncryptor { base: keyed(key, nonce), counter: 0 }
+    }
+
+    /// Encrypt the next chunk in place and return its tag.
+    ///
+    /// Returns an error if the stream already has 2^64 - 1 chunks, only the last can follow.
+    pub fn encrypt_next(&mut self, chunk: &mut [u8]) -> Result<Tag<TAG_LEN>, SpritzCipherError> {
+        let next = self.counter.checked_add(1).ok_or(SpritzCipherError::KeystreamExhausted)?;
+        let tag = seal(&self.base, self.counter, false, chunk);
+        self.counter = next;
+        Ok(tag)
+    }
+
+    /// Encrypt the last chunk in place and return its tag, it may be empty.
+    pub fn encrypt_last(self, chunk: &mut [u8]) -> Tag<TAG_LEN> {
+        seal(&self.base, self.counter, true, chunk)
+    }
+}
+
+/// Decrypts a stream made by a [`StreamEncryptor`], a chunk at a time.
+///
+/// Each chunk must be passed to the same method, `next` or `last`, as when it was encrypted.
+/// A stream that ends without a chunk passing [`decrypt_last`](Self::decrypt_last) was cut
+/// short.
+pub struct StreamDecryptor {
+    base: Sponge,
+    counter: u64,
+}
+
+impl StreamDecryptor {
+    /// Start decrypting the stream with this key and nonce.
+    pub fn new(key: &[u8], nonce: &[u8]) -> StreamDecryptor {
+        StreamDecryptor { base: keyed(key, nonce), counter: 0 }
+    }
+
+    /// Check the next chunk's tag and decrypt it in place.
+    ///
+    /// Returns an error, and leaves `chunk` and the position unchanged, if the tag doesn't
+    /// match.
+    pub fn decrypt_next(&mut self, chunk: &mut [u8], tag: &Tag<TAG_LEN>) -> Result<(), SpritzCipherError> {
+        let next = self.counter.checked_add(1).ok_or(SpritzCipherError::KeystreamExhausted)?;
+        open(&self.base, self.counter, false, chunk, tag)?;
+        self.counter = next;
+        Ok(())
+    }
+
+    /// Check the last chunk's tag and decrypt it in place.
+    ///
+    /// Returns an error, and leaves `chunk` unchanged, if the tag doesn't match.
+    pub fn decrypt_last(self, chunk: &mut [u8], tag: &Tag<TAG_LEN>) -> Result<(), SpritzCipherError> {
+        open(&self.base, self.counter, true, chunk, tag)
+    }
+}
+
+/// Encrypts everything written to it into `inner` (`std`).
+///
+/// [`finish`](Self::finish) must be called to write the last chunk, a writer that is just
+/// dropped leaves a stream the reader rejects as cut short. Once writing to `inner` fails the
+/// stream can't be completed, every later `write` and `finish` returns an error.
+#[cfg(feature = "std")]
+pub struct StreamWriter<W: Write> {
+    inner: W,
+    encryptor: StreamEncryptor,
+    /* Plain text of the chunk being filled, with room for its tag */
+    buf: Zeroizing<Vec<u8>>,
+    failed: bool,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> StreamWriter<W> {
+    /// Encrypt into `inner`, never reuse a nonce with the same key.
+    pub fn new(inner: W, key: &[u8], nonce: &[u8]) -> StreamWriter<W> {
+        StreamWriter {
+            inner,
+            encryptor: StreamEncryptor::new(key, nonce),
+            buf: Zeroizing::new(Vec::with_capacity(FRAME_LEN)),
+            failed: false,
+        }
+    }
+
+    /// Write the last chunk and return the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        let StreamWriter { mut inner, encryptor, mut buf, failed } = self;
+        if failed {
+            return Err(broken());
+        }
+        let tag = encryptor.encrypt_last(&mut buf);
+        buf.extend_from_slice(tag.as_bytes());
+        inner.write_all(&buf)?;
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> Write for StreamWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.failed {
+            return Err(broken());
+        }
+        /* A full chunk is only written once more data comes, as it might be the last */
+        if self.buf.len() == CHUNK_LEN && !data.is_empty() {
+            let tag = self.encryptor.encrypt_next(&mut self.buf).map_err(io::Error::other)?;
+            self.buf.extend_from_slice(tag.as_bytes());
+            let written = self.inner.write_all(&self.buf);
+            self.buf.clear();
+            if let Err(err) = written {
+                /* Part of the chunk may be out, and its number is used up */
+                self.failed = true;
+                return Err(err);
+            }
+        }
+
+        let take = data.len().min(CHUNK_LEN - self.buf.len());
+        self.buf.extend_from_slice(&data[..take]);
+        Ok(take)
+    }
+
+    /// Flushes the inner writer, the chunk being filled is only written when full.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The error a [`StreamWriter`] gives after a failed write.
+#[cfg(feature = "std")]
+fn broken() -> io::Error {
+    io::Error::other("an earlier write to the stream failed")
+}
+
+/// Decrypts a stream written by a [`StreamWriter`] (`std`).
+///
+/// Reads only give out plain text whose tag has been checked. A chunk that fails, or a stream
+/// that ends early, is an `InvalidData` error wrapping
+/// [`SpritzCipherError::AuthenticationFailed`], and so is every read after it.
+#[cfg(feature = "std")]
+pub struct StreamReader<R: Read> {
+    inner: R,
+    /* None once the last chunk has been decrypted */
+    decryptor: Option<StreamDecryptor>,
+    /* A chunk and its tag, and one more byte to tell whether another chunk follows */
+    buf: Zeroizing<Vec<u8>>,
+    /* Bytes of the next chunk read so far */
+    filled: usize,
+    /* The decrypted plain text is buf[pos..plain] */
+    pos: usize,
+    plain: usize,
+    /* The first byte of the next chunk, read with the one being given out */
+    carry: Option<u8>,
+    failed: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> StreamReader<R> {
+    /// Decrypt the stream read from `inner`.
+    pub fn new(inner: R, key: &[u8], nonce: &[u8]) -> StreamReader<R> {
+        StreamReader {
+            inner,
+            decryptor: Some(StreamDecryptor::new(key, nonce)),
+            buf: Zeroizing::new(std::vec![0u8; FRAME_LEN + 1]),
+            filled: 0,
+            pos: 0,
+            plain: 0,
+            carry: None,
+            failed: false,
+        }
+    }
+
+    /// The inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Decrypt the chunk in `buf`, the plain text is left at its start.
+    fn next_chunk(&mut self, decryptor: StreamDecryptor) -> Result<(), SpritzCipherError> {
+        /* A chunk that isn't the last is followed by at least one byte */
+        let last = self.filled <= FRAME_LEN;
+        let len = self.filled.min(FRAME_LEN) - TAG_LEN;
+        let (chunk, rest) = self.buf.split_at_mut(len);
+        let mut tag = [0u8; TAG_LEN];
+        tag.copy_from_slice(&rest[..TAG_LEN]);
+        let tag = Tag::new(tag);
+
+        if last {
+            decryptor.decrypt_last(chunk, &tag)?;
+        } else {
+            let mut decryptor = decryptor;
+            decryptor.decrypt_next(chunk, &tag)?;
+            self.decryptor = Some(decryptor);
+            self.carry = Some(rest[TAG_LEN]);
+        }
+        self.pos = 0;
+        self.plain = len;
+        self.filled = 0;
+        Ok(())
+    }
+
+    /// Fill `buf` with the next chunk, its tag and a byte more if there is one.
+    fn fill(&mut self) -> io::Result<()> {
+        if let Some(byte) = self.carry.take() {
+            self.buf[0] = byte;
+            self.filled = 1;
+        }
+        while self.filled <= FRAME_LEN {
+            match self.inner.read(&mut self.buf[self.filled..]) {
+                Ok(0) => break,
+                Ok(n) => self.filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for StreamReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.failed {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, SpritzCipherError::AuthenticationFailed));
+        }
+        if self.pos == self.plain {
+            let decryptor = match self.decryptor.take() {
+                Some(decryptor) => decryptor,
+                None => return Ok(0),
+            };
+            if let Err(err) = self.fill() {
+                self.decryptor = Some(decryptor);
+                return Err(err);
+            }
+            /* Too short for a tag means the stream was cut */
+            let result = if self.filled < TAG_LEN { Err(SpritzCipherError::AuthenticationFailed) } else { self.next_chunk(decryptor) };
+            if let Err(err) = result {
+                self.failed = true;
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            }
+        }
+
+        let n = out.len().min(self.plain - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
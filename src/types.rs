@@ -0,0 +1,150 @@
+//! Fixed size keys, nonces, tags and digests.
+
+use crate::ct::{Choice, ConstantTimeEq};
+use crate::encoding::{self, Base64, Hex};
+use crate::{SpritzCipherError, SpritzRng};
+
+use core::convert::TryFrom;
+use core::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+macro_rules! byte_newtype {
+    ($(#[$meta:meta])* $name:ident, $default:literal) => {
+        $(#[$meta])*
+        ///
+        /// Wiped on drop, `Debug` doesn't show the bytes and `==` takes constant time.
+        #[derive(Clone, Zeroize, ZeroizeOnDrop)]
+        pub struct $name<const N: usize = $default>([u8; N]);
+
+        impl<const N: usize> $name<N> {
+            /// Wrap `bytes`, the caller's copy is not wiped.
+            pub fn new(bytes: [u8; N]) -> Self {
+                $name(bytes)
+            }
+
+            /// The bytes.
+            pub fn as_bytes(&self) -> &[u8; N] {
+                &self.0
+            }
+
+            /// Parse `2 * N` hex digits, in either case.
+            pub fn from_hex(hex: &str) -> Result<Self, SpritzCipherError> {
+                let mut value = $name([0u8; N]);
+                encoding::decode_hex(hex, &mut value.0)?;
+                Ok(value)
+            }
+
+            /// Parse padded base64 that decodes to `N` bytes.
+            pub fn from_base64(base64: &str) -> Result<Self, SpritzCipherError> {
+                let mut value = $name([0u8; N]);
+                encoding::decode_base64(base64, &mut value.0)?;
+                Ok(value)
+            }
+
+            /// Lower case hex, for use with `{}`.
+            pub fn to_hex(&self) -> Hex<'_> {
+                Hex(&self.0)
+            }
+
+            /// Padded base64, for use with `{}`.
+            pub fn to_base64(&self) -> Base64<'_> {
+                Base64(&self.0)
+            }
+        }
+
+        impl<const N: usize> From<[u8; N]> for $name<N> {
+            fn from(bytes: [u8; N]) -> Self {
+                $name(bytes)
+            }
+        }
+
+        impl<const N: usize> TryFrom<&[u8]> for $name<N> {
+            type Error = SpritzCipherError;
+
+            fn try_from(bytes: &[u8]) -> Result<Self, SpritzCipherError> {
+                if bytes.len() != N {
+                    return Err(SpritzCipherError::InvalidLength { expected: N, actual: bytes.len() });
+                }
+                let mut value = $name([0u8; N]);
+                value.0.copy_from_slice(bytes);
+                Ok(value)
+            }
+        }
+
+        impl<const N: usize> ConstantTimeEq for $name<N> {
+            fn ct_eq(&self, other: &Self) -> Choice {
+                self.0.ct_eq(&other.0)
+            }
+        }
+
+        impl<const N: usize> PartialEq for $name<N> {
+            fn eq(&self, other: &Self) -> bool {
+                self.ct_eq(other).into()
+            }
+        }
+
+        impl<const N: usize> Eq for $name<N> {}
+
+        impl<const N: usize> fmt::Debug for $name<N> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!(stringify!($name), "<{}>(..)"), N)
+            }
+        }
+    };
+}
+
+byte_newtype!(
+    /// A secret key, 32 bytes unless given.
+    SpritzKey, 32
+);
+
+byte_newtype!(
+    /// A nonce (IV), 16 bytes unless given. Never use one twice with the same key.
+    Nonce, 16
+);
+
+byte_newtype!(
+    /// A MAC tag, 32 bytes unless given.
+    Tag, 32
+);
+
+byte_newtype!(
+    /// A hash digest, 32 bytes unless given.
+    Digest, 32
+);
+
+impl<const N: usize> SpritzKey<N> {
+    /// A new key from `rng`, which must be seeded with real entropy.
+    pub fn generate(rng: &mut SpritzRng) -> Self {
+        let mut key = SpritzKey([0u8; N]);
+        rng.fill_bytes(&mut key.0);
+        key
+    }
+}
+
+impl<const N: usize> Nonce<N> {
+    /// A new nonce from `rng`.
+    pub fn generate(rng: &mut SpritzRng) -> Self {
+        let mut nonce = Nonce([0u8; N]);
+        rng.fill_bytes(&mut nonce.0);
+        nonce
+    }
+}
+
+impl<const N: usize> Tag<N> {
+    /// Filled in by the MAC.
+    pub(crate) fn squeeze(fill: impl FnOnce(&mut [u8])) -> Self {
+        let mut tag = Tag([0u8; N]);
+        fill(&mut tag.0);
+        tag
+    }
+}
+
+impl<const N: usize> Digest<N> {
+    /// Filled in by the hash.
+    pub(crate) fn squeeze(fill: impl FnOnce(&mut [u8])) -> Self {
+        let mut digest = Digest([0u8; N]);
+        fill(&mut digest.0);
+        digest
+    }
+}
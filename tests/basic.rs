@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 
 #[cfg(test)]
 mod basic_tests {
@@ -13,45 +15,45 @@ mod basic_tests {
         
 
         /* Data to input */
-        let mut msg: [u8;BUFFER_SIZE] = ['A' as u8;BUFFER_SIZE];
+        let mut msg: [u8;BUFFER_SIZE] = [b'A';BUFFER_SIZE];
         let mut key = [0u8;KEY_SIZE];
         thread_rng().fill(&mut key);
         thread_rng().fill(&mut msg);
 
-        let mut buf = [0 as u8;BUFFER_SIZE]; /* Output buffer */
+        let mut buf = [0_u8;BUFFER_SIZE]; /* Output buffer */
 
 
         let mut context = SpritzCipherContext::setup(&key);
-        context.crypt(&msg, &mut buf);
+        context.crypt(&msg, &mut buf).unwrap();
 
 
         let mut context = SpritzCipherContext::setup(&key);
-        let buf2 = buf.clone();
-        context.crypt(&buf2, &mut buf);
+        let buf2 = buf;
+        context.crypt(&buf2, &mut buf).unwrap();
 
         print!("Key: ");
         for val in key.iter(){
             print!("{}, ",val);
         }
-        println!("");
+        println!();
 
         print!("Input: ");
         for val in msg.iter(){
             print!("{}, ",val);
         }
-        println!("");
+        println!();
 
         print!("Output: ");
         for val in buf2.iter(){
             print!("{}, ",val);
         }
-        println!("");
+        println!();
 
         print!("Decrypt: ");
         for val in buf.iter(){
             print!("{}, ",val);
         }
-        println!("");
+        println!();
 
 
         /* Check the output */
@@ -75,7 +77,7 @@ mod basic_tests {
         0xa5, 0xf9, 0xfe, 0x69, 0x44, 0x65, 0x3c, 0xd5,
         0x0e, 0x66, 0xbf, 0x18, 0x9c, 0x63, 0xf6, 0x99
         ];
-        let test_data: [u8; 7] = [ 'a' as u8, 'r' as u8, 'c' as u8, 'f' as u8, 'o' as u8, 'u' as u8, 'r' as u8 ];
+        let test_data: [u8; 7] = [ b'a', b'r', b'c', b'f', b'o', b'u', b'r' ];
 
         let mut digest = [0u8;BUFFER_SIZE]; /* Output buffer */
         let mut digest_2 = [0u8;BUFFER_SIZE]; /* Output buffer for chunk by chunk API */
@@ -86,9 +88,9 @@ mod basic_tests {
         for byte in test_data.iter() {
             context.hash_update(&[*byte]);
         }
-        context.hash_final(&mut digest_2);
+        context.hash_final(&mut digest_2).unwrap();
 
-        SpritzCipherContext::hash(&mut digest, &test_data);
+        SpritzCipherContext::hash(&mut digest, &test_data).unwrap();
 
         /* Check the output */
         assert_eq!(SpritzCipherContext::compare(&digest, &test_vector).unwrap(), 0);
@@ -101,7 +103,7 @@ mod basic_tests {
     fn mac() {
 
         /* Data to input */
-        let mut msg: [u8;3] = ['A' as u8, 'B' as u8, 'C' as u8];
+        let mut msg: [u8;3] = [b'A', b'B', b'C'];
         let mut key: [u8;3] = [0x00, 0x01, 0x02];
 
         /* Test vectors */
@@ -118,10 +120,41 @@ mod basic_tests {
         let mut digest = [0u8;BUFFER_SIZE]; /* Output buffer */
 
         use spritz_cipher::SpritzCipherContext;
-        SpritzCipherContext::mac(&mut digest, &mut msg, &mut key);
+        SpritzCipherContext::mac(&mut digest, &mut msg, &mut key).unwrap();
 
         /* Check the output */
         assert_eq!(SpritzCipherContext::compare(&digest, &test_vector).unwrap(), 0);
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn long_digest_lengths() {
+
+        use spritz_cipher::{LengthEncoding, SpritzCipherContext};
+
+        /* Up to 255 bytes both encodings match the C reference */
+        for len in [0usize, 1, 32, 255].iter() {
+            let mut compat = vec![0u8; *len];
+            let mut extended = vec![0u8; *len];
+            SpritzCipherContext::hash(&mut compat, b"ABC").unwrap();
+            SpritzCipherContext::hash_with(&mut extended, b"ABC", LengthEncoding::Extended).unwrap();
+            assert_eq!(compat, extended);
+        }
+
+        /* A single length byte would truncate 288 and 300 to 32 and 44 */
+        for (long, short) in [(256 + 32usize, 32usize), (300, 44), (65536 + 44, 44)].iter() {
+            let mut long_digest = vec![0u8; *long];
+            let mut short_digest = vec![0u8; *short];
+            SpritzCipherContext::hash_with(&mut long_digest, b"ABC", LengthEncoding::Extended).unwrap();
+            SpritzCipherContext::hash(&mut short_digest, b"ABC").unwrap();
+            assert_ne!(&long_digest[..*short], &short_digest[..]);
+
+            let mut digest = vec![0u8; 64];
+            SpritzCipherContext::hash(&mut digest, b"ABC").unwrap();
+            assert_ne!(&long_digest[..64], &digest[..]);
+        }
+
+        let mut digest = [0u8; 256];
+        assert!(SpritzCipherContext::hash_with(&mut digest, b"ABC", LengthEncoding::Compat).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod batch {
+    use spritz_cipher::{SpritzCipherError, SpritzHasher, SpritzMac};
+    use std::io::IoSlice;
+
+    /// Messages of assorted lengths, crossing the 64 byte shuffle point.
+    fn messages(count: usize) -> Vec<Vec<u8>> {
+        (0..count).map(|n| (0..(n * 13) % 150).map(|k| (k + n) as u8).collect()).collect()
+    }
+
+    #[test]
+    fn hash_batch_matches_hash() {
+        /* Counts around the lane width, so groups and leftovers are both covered */
+        for count in [0, 1, 3, 4, 5, 8, 11] {
+            let msgs = messages(count);
+            let refs: Vec<&[u8]> = msgs.iter().map(|m| &m[..]).collect();
+            let mut out = vec![0u8; count * 20];
+            SpritzHasher::hash_batch(&mut out, 20, &refs).unwrap();
+
+            for (msg, digest) in msgs.iter().zip(out.chunks(20)) {
+                let mut expected = [0u8; 20];
+                SpritzHasher::hash(&mut expected, msg).unwrap();
+                assert_eq!(digest, expected, "{} messages", count);
+            }
+        }
+    }
+
+    #[test]
+    fn vectored() {
+        let whole = b"scattered across three buffers";
+        let bufs = [IoSlice::new(&whole[..9]), IoSlice::new(&whole[9..]), IoSlice::new(b"")];
+        let mut expected = [0u8; 32];
+        SpritzHasher::hash(&mut expected, whole).unwrap();
+
+        let mut digest = [0u8; 32];
+        SpritzHasher::hash_vectored(&mut digest, &bufs).unwrap();
+        assert_eq!(digest, expected);
+
+        /* Messages with different numbers of pieces, in lanes and left over */
+        let one = [IoSlice::new(whole)];
+        let many: Vec<IoSlice> = whole.chunks(4).map(IoSlice::new).collect();
+        let messages: [&[IoSlice]; 5] = [&bufs, &one, &many, &[], &bufs];
+        let mut out = [0u8; 5 * 32];
+        SpritzHasher::hash_batch_vectored(&mut out, 32, &messages).unwrap();
+        let mut empty = [0u8; 32];
+        SpritzHasher::hash(&mut empty, b"").unwrap();
+        for (n, digest) in out.chunks(32).enumerate() {
+            assert_eq!(digest, if n == 3 { empty } else { expected }, "message {}", n);
+        }
+
+        let mut mac = SpritzMac::new(b"key");
+        mac.update_vectored(&bufs);
+        let mut tag = [0u8; 32];
+        mac.finalize(&mut tag).unwrap();
+        let mut expected = [0u8; 32];
+        SpritzMac::mac(&mut expected, whole, b"key").unwrap();
+        assert_eq!(tag, expected);
+    }
+
+    #[test]
+    fn mac_batch_matches_mac() {
+        let msgs = messages(9);
+        let refs: Vec<&[u8]> = msgs.iter().map(|m| &m[..]).collect();
+        let keys: Vec<Vec<u8>> = (0..9).map(|n| vec![n as u8; n * 10]).collect();
+        let key_refs: Vec<&[u8]> = keys.iter().map(|k| &k[..]).collect();
+
+        let mut shared = vec![0u8; 9 * 32];
+        SpritzMac::mac_batch(&mut shared, 32, &refs, b"shared key").unwrap();
+        let mut keyed = vec![0u8; 9 * 32];
+        SpritzMac::mac_batch_keyed(&mut keyed, 32, &refs, &key_refs).unwrap();
+
+        for n in 0..9 {
+            let mut expected = [0u8; 32];
+            SpritzMac::mac(&mut expected, &msgs[n], b"shared key").unwrap();
+            assert_eq!(shared[n * 32..][..32], expected, "shared key, message {}", n);
+            SpritzMac::mac(&mut expected, &msgs[n], &keys[n]).unwrap();
+            assert_eq!(keyed[n * 32..][..32], expected, "own key, message {}", n);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let msgs: [&[u8]; 2] = [b"a", b"b"];
+        let mut out = [0u8; 63];
+        assert_eq!(
+            SpritzHasher::hash_batch(&mut out, 32, &msgs),
+            Err(SpritzCipherError::LengthsDontMatch { expected: 64, actual: 63 })
+        );
+        let mut out = [0u8; 2 * 256];
+        assert_eq!(
+            SpritzHasher::hash_batch(&mut out, 256, &msgs),
+            Err(SpritzCipherError::DigestTooLong { max: 255, actual: 256 })
+        );
+        let mut out = [0u8; 64];
+        assert_eq!(
+            SpritzMac::mac_batch_keyed(&mut out, 32, &msgs, &[b"one key"]),
+            Err(SpritzCipherError::LengthsDontMatch { expected: 2, actual: 1 })
+        );
+        assert_eq!(out, [0u8; 64]);
+        assert_eq!(SpritzHasher::hash_batch(&mut [], 0, &msgs), Ok(()));
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(deprecated)]
+
 #[cfg(test)]
 mod c_check {
     #[test]
@@ -7,7 +9,7 @@ mod c_check {
         use rand::prelude::*;
         use libc::size_t;
 
-        extern  {
+        extern "C" {
             /*fn spritz_mac(digest: *mut u8, digestLen: size_t,
                             msg: *const u8, msgLen: size_t,
                             key: *const u8, keyLen: size_t);*/
@@ -19,7 +21,7 @@ mod c_check {
         
 
         let mut check = Vec::new();
-        check.extend_from_slice(&mut [0f64;256]);
+        check.extend_from_slice(&[0f64;256]);
 
         //loop {
             const BUFFER_SIZE: usize = 512;
@@ -27,16 +29,16 @@ mod c_check {
             const DIGEST_SIZE: usize = 32;
     
             /* Data to input */
-            let mut msg: [u8;BUFFER_SIZE] = ['A' as u8;BUFFER_SIZE];
+            let mut msg: [u8;BUFFER_SIZE] = [b'A';BUFFER_SIZE];
             let mut key = [0u8;KEY_SIZE];
             thread_rng().fill(&mut key);
             thread_rng().fill(&mut msg);
 
-            let mut mac_1 = [0 as u8;DIGEST_SIZE]; /* Output buffer */
-            let mut mac_2 = [0 as u8;DIGEST_SIZE]; /* Output buffer */
-            let mut mac_3 = [0 as u8;DIGEST_SIZE]; /* Output buffer */
+            let mut mac_1 = [0_u8;DIGEST_SIZE]; /* Output buffer */
+            let _mac_2 = [0_u8;DIGEST_SIZE]; /* Output buffer */
+            let mut mac_3 = [0_u8;DIGEST_SIZE]; /* Output buffer */
 
-            SpritzCipherContext::mac(&mut mac_1, &mut msg, &mut key);
+            SpritzCipherContext::mac(&mut mac_1, &mut msg, &mut key).unwrap();
 
             unsafe {
                 //spritz_mac(mac_2.as_mut_ptr(),mac_2.len() as size_t,msg.as_ptr(),msg.len() as size_t,key.as_ptr(),key.len() as size_t);
@@ -61,14 +63,14 @@ mod c_check {
             for val in mac_1.iter(){
                 print!("{:X}",val);
             }
-            println!("");
+            println!();
 
             print!("MAC 2: ");
             for val in mac_3.iter(){
                 print!("{:X}",val);
             }
-            println!("");
-            println!("");
+            println!();
+            println!();
 
             /* Check the output */
             //assert_eq!(SpritzCipherContext::compare(&mac_1, &mac_2), 0);
@@ -83,4 +85,35 @@ mod c_check {
 
         //}
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn compare_hash_many_shuffles() {
+
+        use spritz_cipher::SpritzCipherContext;
+        use rand::prelude::*;
+        use libc::size_t;
+
+        extern "C" {
+            fn spritz_hash(out: *mut u8, outlen: size_t,
+                            msg: *const u8, msglen: size_t) -> i32;
+        }
+
+        /* The C crush branches on the S-box values, ours uses a mask.
+         * Every 64 bytes absorbed runs a shuffle (3 whips and 2 crushes), so long
+         * random messages compare a lot of crushes.
+         */
+        for len in [0usize, 1, 63, 64, 65, 128, 1000, 4096].iter() {
+            let mut msg = vec![0u8; *len];
+            thread_rng().fill(&mut msg[..]);
+
+            let mut digest_1 = [0u8; 32];
+            let mut digest_2 = [0u8; 32];
+            SpritzCipherContext::hash(&mut digest_1, &msg).unwrap();
+            unsafe {
+                spritz_hash(digest_2.as_mut_ptr(), digest_2.len() as size_t, msg.as_ptr(), msg.len() as size_t);
+            }
+
+            assert_eq!(digest_1, digest_2);
+        }
+    }
+}
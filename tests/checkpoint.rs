@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod checkpoint {
+    #![allow(deprecated)]
+
+    use spritz_cipher::checkpoint::{self, Checkpoint, Checkpointer};
+    use spritz_cipher::{SpritzCipher, SpritzCipherContext, SpritzCipherError};
+
+    const LEN: usize = 10_000;
+    const INTERVAL: usize = 1000;
+
+    fn plain() -> Vec<u8> {
+        (0..LEN).map(|n| (n * 31) as u8).collect()
+    }
+
+    /// The stream encrypted in `piece` byte calls, and the checkpoints recorded.
+    fn encrypt(nonce: Option<&[u8]>, piece: usize) -> (Vec<u8>, Vec<Checkpoint>) {
+        let mut data = plain();
+        let mut index = Vec::new();
+        let mut cipher = Checkpointer::new(b"key", nonce, INTERVAL).unwrap();
+        for chunk in data.chunks_mut(piece) {
+            cipher.apply_keystream(chunk, |checkpoint| index.push(checkpoint));
+        }
+        assert_eq!(cipher.position(), LEN as u64);
+        (data, index)
+    }
+
+    #[test]
+    fn same_stream_as_crypt() {
+        let (with_nonce, _) = encrypt(Some(b"nonce"), LEN);
+        let mut expected = plain();
+        SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut expected);
+        assert!(with_nonce == expected);
+
+        /* Data encrypted with the old context decrypts the same way */
+        let (without, _) = encrypt(None, LEN);
+        let mut legacy = vec![0u8; LEN];
+        SpritzCipherContext::setup(b"key").crypt(&plain(), &mut legacy).unwrap();
+        assert!(without == legacy);
+    }
+
+    #[test]
+    fn checkpoints_dont_depend_on_the_calls() {
+        let (data, index) = encrypt(Some(b"nonce"), LEN);
+        let positions: Vec<u64> = index.iter().map(|checkpoint| checkpoint.position()).collect();
+        assert_eq!(positions, (1..=10).map(|n| n * INTERVAL as u64).collect::<Vec<_>>());
+
+        for piece in [1, 999, 1000, 1001, 4096] {
+            let (other_data, other_index) = encrypt(Some(b"nonce"), piece);
+            assert!(other_data == data, "{} byte pieces", piece);
+            assert_eq!(other_index, index, "{} byte pieces", piece);
+        }
+    }
+
+    #[test]
+    fn seek_matches_decrypting_from_the_start() {
+        let (data, index) = encrypt(Some(b"nonce"), LEN);
+        for position in [0, 1, 999, 1000, 1001, 5500, 9999, 10_000] {
+            let mut tail = data[position..].to_vec();
+            checkpoint::seek(b"key", Some(b"nonce"), &index, position as u64).unwrap().apply_keystream(&mut tail);
+            assert!(tail[..] == plain()[position..], "from {}", position);
+
+            /* Without an index it starts from the beginning */
+            let mut tail = data[position..].to_vec();
+            checkpoint::seek(b"key", Some(b"nonce"), &[], position as u64).unwrap().apply_keystream(&mut tail);
+            assert!(tail[..] == plain()[position..], "from {} without checkpoints", position);
+        }
+
+        /* Resuming from each checkpoint, in any order */
+        for checkpoint in index.iter().rev() {
+            let start = checkpoint.position() as usize;
+            let mut tail = data[start..].to_vec();
+            checkpoint::resume(b"key", Some(b"nonce"), checkpoint).unwrap().apply_keystream(&mut tail);
+            assert!(tail[..] == plain()[start..], "from {}", start);
+        }
+    }
+
+    #[test]
+    fn tampering_is_rejected() {
+        let (_, index) = encrypt(Some(b"nonce"), LEN);
+        let checkpoint = &index[2];
+
+        for at in [0, 7, 8, 100, Checkpoint::LEN - 33, Checkpoint::LEN - 1] {
+            let mut bytes = *checkpoint.as_bytes();
+            bytes[at] ^= 1;
+            let changed = Checkpoint::from_bytes(&bytes).unwrap();
+            assert_eq!(
+                checkpoint::resume(b"key", Some(b"nonce"), &changed).err(),
+                Some(SpritzCipherError::AuthenticationFailed),
+                "byte {}",
+                at
+            );
+        }
+
+        /* A checkpoint of another stream */
+        assert!(checkpoint::resume(b"key2", Some(b"nonce"), checkpoint).is_err());
+        assert!(checkpoint::resume(b"key", Some(b"nonce2"), checkpoint).is_err());
+        assert!(checkpoint::resume(b"key", None, checkpoint).is_err());
+        assert!(checkpoint::seek(b"key", None, &index, 5000).is_err());
+    }
+
+    #[test]
+    fn stored_checkpoints() {
+        let (_, index) = encrypt(None, LEN);
+        let stored = index[0].as_bytes().to_vec();
+        let read = Checkpoint::from_bytes(&stored).unwrap();
+        assert_eq!(read, index[0]);
+        assert_eq!(format!("{:?}", read), "Checkpoint { position: 1000, .. }");
+
+        assert_eq!(
+            Checkpoint::from_bytes(&stored[1..]),
+            Err(SpritzCipherError::InvalidLength { expected: Checkpoint::LEN, actual: Checkpoint::LEN - 1 })
+        );
+        assert_eq!(
+            Checkpointer::new(b"key", None, 0).err(),
+            Some(SpritzCipherError::TooSmall { min: 1, actual: 0 })
+        );
+    }
+}
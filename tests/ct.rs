@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod ct {
+    use spritz_cipher::ct::{self, Choice, ConditionallySelectable, ConstantTimeEq};
+
+    #[test]
+    fn choice() {
+        assert!(bool::from(Choice::from(1)));
+        assert!(!bool::from(Choice::from(0)));
+        /* Only the lowest bit counts */
+        assert_eq!(Choice::from(0xfe).unwrap_u8(), 0);
+        assert_eq!(Choice::from(0xff).unwrap_u8(), 1);
+
+        let t = Choice::from(1);
+        let f = Choice::from(0);
+        assert_eq!((t & f).unwrap_u8(), 0);
+        assert_eq!((t | f).unwrap_u8(), 1);
+        assert_eq!((!t).unwrap_u8(), 0);
+        assert_eq!((!f).unwrap_u8(), 1);
+    }
+
+    #[test]
+    fn integers() {
+        for a in 0..=255u8 {
+            for b in [0u8, 1, 0x7f, 0x80, 0xff, a].iter() {
+                assert_eq!(bool::from(a.ct_eq(b)), a == *b);
+                assert_eq!(bool::from(a.ct_ne(b)), a != *b);
+            }
+        }
+        for (a, b) in [(0u64, 0u64), (1, 0), (u64::MAX, u64::MAX), (1 << 63, 0), (5, 5)].iter() {
+            assert_eq!(bool::from(a.ct_eq(b)), a == b);
+            assert_eq!(bool::from((*a as u32).ct_eq(&(*b as u32))), *a as u32 == *b as u32);
+            assert_eq!(bool::from((*a as usize).ct_eq(&(*b as usize))), *a as usize == *b as usize);
+        }
+    }
+
+    #[test]
+    fn slices() {
+        let a = [1u8, 2, 3, 4];
+        let mut b = a;
+        assert!(ct::ct_eq(&a, &b));
+        assert!(bool::from(a.ct_eq(&b)));
+        assert!(ct::ct_eq(&[], &[]));
+
+        b[3] ^= 0x80;
+        assert!(!ct::ct_eq(&a, &b));
+        assert!(!bool::from(a.ct_eq(&b)));
+
+        /* Different lengths are never equal */
+        assert!(!ct::ct_eq(&a, &a[..3]));
+        assert!(!ct::ct_eq(&a[..0], &a[..1]));
+    }
+
+    #[test]
+    fn length_hiding() {
+        let secret = b"correct horse battery staple";
+        assert!(ct::ct_eq_length_hiding(secret, secret));
+        assert!(!ct::ct_eq_length_hiding(secret, b"correct horse battery stapl"));
+        assert!(!ct::ct_eq_length_hiding(secret, b"correct horse battery staplee"));
+        assert!(!ct::ct_eq_length_hiding(secret, b"correct horse battery staplf"));
+        assert!(!ct::ct_eq_length_hiding(secret, b""));
+        assert!(!ct::ct_eq_length_hiding(b"", secret));
+        assert!(ct::ct_eq_length_hiding(b"", b""));
+        assert!(!ct::ct_eq_length_hiding(b"a", b"aa"));
+    }
+
+    #[test]
+    fn select_and_copy() {
+        assert_eq!(u8::conditional_select(&1, &2, Choice::from(0)), 1);
+        assert_eq!(u8::conditional_select(&1, &2, Choice::from(1)), 2);
+        assert_eq!(u64::conditional_select(&u64::MAX, &7, Choice::from(1)), 7);
+
+        let mut x = 10u32;
+        x.conditional_assign(&20, Choice::from(0));
+        assert_eq!(x, 10);
+        x.conditional_assign(&20, Choice::from(1));
+        assert_eq!(x, 20);
+
+        let (mut a, mut b) = (1u16, 2u16);
+        u16::conditional_swap(&mut a, &mut b, Choice::from(1));
+        assert_eq!((a, b), (2, 1));
+
+        let mut dst = [0u8; 4];
+        ct::conditional_copy(&mut dst, &[1, 2, 3, 4], Choice::from(0)).unwrap();
+        assert_eq!(dst, [0; 4]);
+        ct::conditional_copy(&mut dst, &[1, 2, 3, 4], Choice::from(1)).unwrap();
+        assert_eq!(dst, [1, 2, 3, 4]);
+        assert!(ct::conditional_copy(&mut dst, &[1, 2, 3], Choice::from(1)).is_err());
+
+        let mut out = [0u8; 2];
+        ct::conditional_select(&mut out, &[1, 2], &[3, 4], Choice::from(1)).unwrap();
+        assert_eq!(out, [3, 4]);
+        assert!(ct::conditional_select(&mut out, &[1, 2], &[3], Choice::from(1)).is_err());
+    }
+
+    #[cfg(feature = "subtle")]
+    #[test]
+    fn subtle_interop() {
+        let choice: subtle::Choice = Choice::from(1).into();
+        assert_eq!(choice.unwrap_u8(), 1);
+        let back: Choice = subtle::Choice::from(0).into();
+        assert_eq!(back.unwrap_u8(), 0);
+    }
+}
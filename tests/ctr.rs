@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod ctr {
+    use spritz_cipher::{Nonce, SpritzCipher, SpritzCipherError, SpritzCtr, SpritzKey, SpritzKeySchedule};
+
+    const BLOCK: usize = SpritzCtr::BLOCK_LEN;
+
+    /// The keystream from the start, in one call so whole groups go through the lanes.
+    fn keystream(len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; len];
+        SpritzCtr::new(b"key", b"nonce").apply_keystream(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn any_split_gives_the_same_keystream() {
+        let expected = keystream(9 * BLOCK + 123);
+
+        /* Small pieces only ever run one block at a time */
+        for piece in [1, 1000, BLOCK - 1, BLOCK, 3 * BLOCK + 7, 5 * BLOCK] {
+            let mut ctr = SpritzCtr::new(b"key", b"nonce");
+            let mut out = vec![0u8; expected.len()];
+            for chunk in out.chunks_mut(piece) {
+                ctr.apply_keystream(chunk).unwrap();
+            }
+            assert_eq!(ctr.current_pos(), expected.len() as u64);
+            assert!(out == expected, "{} byte pieces", piece);
+        }
+    }
+
+    #[test]
+    fn seek() {
+        let expected = keystream(6 * BLOCK);
+        let mut ctr = SpritzCtr::new(b"key", b"nonce");
+
+        for pos in [0, 1, BLOCK - 1, BLOCK, BLOCK + 1, 5 * BLOCK - 3, 100, 2 * BLOCK] {
+            for len in [0, 1, 10, BLOCK, 4 * BLOCK - 1] {
+                let len = len.min(expected.len() - pos);
+                let mut out = vec![0u8; len];
+                ctr.seek(pos as u64);
+                ctr.apply_keystream(&mut out).unwrap();
+                assert!(out[..] == expected[pos..pos + len], "{} bytes at {}", len, pos);
+
+                ctr.apply_keystream_at(pos as u64, &mut out).unwrap();
+                assert!(out.iter().all(|&b| b == 0), "{} bytes at {}, twice", len, pos);
+            }
+        }
+
+        /* Carrying on after a seek, from the middle of a block */
+        ctr.seek(BLOCK as u64 - 5);
+        let mut out = [0u8; 20];
+        ctr.apply_keystream(&mut out[..10]).unwrap();
+        ctr.apply_keystream(&mut out[10..]).unwrap();
+        assert_eq!(out[..], expected[BLOCK - 5..BLOCK + 15]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let msg: Vec<u8> = (0..5 * BLOCK + 77).map(|n| n as u8).collect();
+        let mut buf = msg.clone();
+        SpritzCtr::new(b"key", b"nonce").apply_keystream(&mut buf).unwrap();
+        assert!(buf != msg);
+
+        /* Decrypt the blocks out of order */
+        let ctr = SpritzCtr::new(b"key", b"nonce");
+        let mut chunks: Vec<(usize, &mut [u8])> = buf.chunks_mut(BLOCK).enumerate().collect();
+        chunks.reverse();
+        for (n, chunk) in chunks {
+            ctr.apply_keystream_at((n * BLOCK) as u64, chunk).unwrap();
+        }
+        assert!(buf == msg);
+    }
+
+    #[test]
+    fn keys_and_nonces() {
+        let expected = keystream(100);
+
+        let mut out = [0u8; 100];
+        SpritzKeySchedule::new(b"key").ctr(b"nonce").apply_keystream(&mut out).unwrap();
+        assert_eq!(out[..], expected[..]);
+
+        let key = SpritzKey::new(*b"key");
+        let nonce = Nonce::new(*b"nonce");
+        let mut out = [0u8; 100];
+        SpritzCtr::from_key_and_nonce(&key, &nonce).apply_keystream(&mut out).unwrap();
+        assert_eq!(out[..], expected[..]);
+
+        for (key, nonce) in [(&b"key"[..], &b"nonce2"[..]), (b"key2", b"nonce"), (b"keynonce", b"")] {
+            let mut out = [0u8; 100];
+            SpritzCtr::new(key, nonce).apply_keystream(&mut out).unwrap();
+            assert_ne!(out[..], expected[..]);
+        }
+
+        /* Not the plain keystream */
+        let mut out = [0u8; 100];
+        SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut out);
+        assert_ne!(out[..], expected[..]);
+    }
+
+    #[test]
+    fn end_of_keystream() {
+        let mut ctr = SpritzCtr::new(b"key", b"nonce");
+        ctr.seek(u64::MAX - 5);
+        let mut out = [0u8; 10];
+        assert_eq!(ctr.apply_keystream(&mut out), Err(SpritzCipherError::KeystreamExhausted));
+        assert_eq!(out, [0u8; 10]);
+        assert_eq!(ctr.current_pos(), u64::MAX - 5);
+
+        ctr.apply_keystream(&mut out[..5]).unwrap();
+        assert_eq!(ctr.current_pos(), u64::MAX);
+        assert!(ctr.apply_keystream_at(u64::MAX, &mut [0u8; 1]).is_err());
+        assert!(ctr.apply_keystream_at(u64::MAX, &mut []).is_ok());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel() {
+        let expected = keystream(13 * BLOCK + 5);
+        let mut ctr = SpritzCtr::new(b"key", b"nonce");
+        for (start, len) in [(0, 13 * BLOCK + 5), (7, 9 * BLOCK), (3 * BLOCK, 2 * BLOCK + 1), (100, 50)] {
+            let mut out = vec![0u8; len];
+            ctr.seek(start as u64);
+            ctr.apply_keystream_parallel(&mut out).unwrap();
+            assert!(out[..] == expected[start..start + len], "{} bytes at {}", len, start);
+            assert_eq!(ctr.current_pos(), (start + len) as u64);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+mod error {
+    use spritz_cipher::{ct, SpritzCipher, SpritzCipherError, SpritzHasher, MAX_DIGEST_SIZE};
+
+    #[test]
+    fn lengths_are_reported() {
+        let mut out = [0u8; 3];
+        let err = SpritzCipher::new(b"key").crypt(&[0u8; 5], &mut out).unwrap_err();
+        assert_eq!(err, SpritzCipherError::LengthsDontMatch { expected: 5, actual: 3 });
+
+        let mut digest = [0u8; 300];
+        let err = SpritzHasher::hash(&mut digest, b"msg").unwrap_err();
+        assert_eq!(err, SpritzCipherError::DigestTooLong { max: MAX_DIGEST_SIZE, actual: 300 });
+
+        let err = ct::conditional_select(&mut [0u8; 4], &[0u8; 4], &[0u8; 2], ct::Choice::from(1)).unwrap_err();
+        assert_eq!(err, SpritzCipherError::LengthsDontMatch { expected: 4, actual: 2 });
+    }
+
+    #[test]
+    fn display() {
+        let err = SpritzCipherError::LengthsDontMatch { expected: 5, actual: 3 };
+        assert_eq!(err.to_string(), "lengths don't match: expected 5 bytes, got 3");
+
+        let err = SpritzCipherError::DigestTooLong { max: 255, actual: 300 };
+        assert_eq!(err.to_string(), "digest too long: 300 bytes, at most 255 fit the length byte");
+
+        let err = SpritzCipherError::TooSmall { min: 2, actual: 1 };
+        assert_eq!(err.to_string(), "too small: 1, at least 2 is needed");
+
+        let err = SpritzCipherError::KeystreamExhausted;
+        assert_eq!(err.to_string(), "keystream exhausted: the position would pass 2^64 bytes");
+        assert_eq!(SpritzCipherError::AuthenticationFailed.to_string(), "authentication failed");
+    }
+
+    #[test]
+    fn std_error() {
+        fn hash_it() -> Result<(), Box<dyn std::error::Error>> {
+            let mut digest = [0u8; 256];
+            SpritzHasher::hash(&mut digest, b"msg")?;
+            Ok(())
+        }
+
+        let err = hash_it().unwrap_err();
+        assert!(err.downcast_ref::<SpritzCipherError>().is_some());
+    }
+}
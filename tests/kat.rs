@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod kat {
+    use spritz_cipher::tree::{self, TreeHasher, TreeParams};
+    use spritz_cipher::{encoding, LengthEncoding, SpritzCipher, SpritzCtr, SpritzHasher, SpritzMac, SpritzRng, MAX_DIGEST_SIZE};
+    use std::collections::HashMap;
+
+    const VECTORS: &str = include_str!("vectors/spritz.kat");
+
+    /// One block of the KAT file, `line` is where it starts for error messages.
+    struct Vector {
+        line: usize,
+        fields: HashMap<String, String>,
+    }
+
+    impl Vector {
+        fn text(&self, name: &str) -> &str {
+            self.fields
+                .get(name)
+                .unwrap_or_else(|| panic!("line {}: missing field '{}'", self.line, name))
+        }
+
+        fn bytes(&self, name: &str) -> Vec<u8> {
+            parse_value(self.text(name)).unwrap_or_else(|| panic!("line {}: bad value for '{}'", self.line, name))
+        }
+
+        fn number(&self, name: &str) -> usize {
+            self.text(name).parse().unwrap_or_else(|_| panic!("line {}: bad number for '{}'", self.line, name))
+        }
+
+        fn words(&self, name: &str) -> Vec<u32> {
+            self.text(name)
+                .split_whitespace()
+                .map(|w| u32::from_str_radix(w, 16).unwrap_or_else(|_| panic!("line {}: bad word '{}'", self.line, w)))
+                .collect()
+        }
+    }
+
+    fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+        let mut bytes = vec![0u8; hex.len() / 2];
+        encoding::decode_hex(hex, &mut bytes).ok()?;
+        Some(bytes)
+    }
+
+    /// `414243`, `"ABC"`, `00 * 300` or nothing.
+    fn parse_value(value: &str) -> Option<Vec<u8>> {
+        if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            return Some(value.as_bytes()[1..value.len() - 1].to_vec());
+        }
+        if let Some((byte, count)) = value.split_once('*') {
+            let byte = parse_hex(byte.trim())?;
+            let count: usize = count.trim().parse().ok()?;
+            return if byte.len() == 1 { Some(vec![byte[0]; count]) } else { None };
+        }
+        parse_hex(value)
+    }
+
+    fn parse(text: &str) -> Vec<Vector> {
+        let mut vectors = Vec::new();
+        let mut current: Option<Vector> = None;
+
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.is_empty() {
+                vectors.extend(current.take());
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .unwrap_or_else(|| panic!("line {}: expected 'name = value'", line_no + 1));
+            let vector = current.get_or_insert_with(|| Vector { line: line_no + 1, fields: HashMap::new() });
+            vector.fields.insert(name.trim().to_string(), value.trim().to_string());
+        }
+        vectors.extend(current.take());
+
+        vectors
+    }
+
+    fn check_prefix(vector: &Vector, actual: &[u8]) {
+        let expected = vector.bytes("output");
+        assert!(expected.len() <= actual.len(), "line {}: output longer than the result", vector.line);
+        assert_eq!(&actual[..expected.len()], &expected[..], "line {}", vector.line);
+    }
+
+    fn keystream(mut cipher: SpritzCipher, len: usize) -> Vec<u8> {
+        /* XOR-ing zeros gives the raw keystream */
+        let mut out = vec![0u8; len];
+        cipher.apply_keystream(&mut out);
+        out
+    }
+
+    fn run(vector: &Vector) {
+        match vector.text("mode") {
+            "stream" => {
+                let len = vector.bytes("output").len();
+                let cipher = SpritzCipher::new(&vector.bytes("key"));
+                check_prefix(vector, &keystream(cipher, len));
+            }
+            "stream_iv" => {
+                let len = vector.bytes("output").len();
+                let cipher = SpritzCipher::with_nonce(&vector.bytes("key"), &vector.bytes("nonce"));
+                check_prefix(vector, &keystream(cipher, len));
+            }
+            "hash" => {
+                let msg = vector.bytes("msg");
+                let mut digest = vec![0u8; vector.number("length")];
+                if digest.len() <= MAX_DIGEST_SIZE {
+                    SpritzHasher::hash(&mut digest, &msg).unwrap();
+                    check_prefix(vector, &digest);
+                } else {
+                    assert!(SpritzHasher::hash(&mut digest, &msg).is_err(), "line {}", vector.line);
+                }
+                SpritzHasher::hash_with(&mut digest, &msg, LengthEncoding::Extended).unwrap();
+                check_prefix(vector, &digest);
+
+                /* Chunk by chunk must give the same answer */
+                let mut hasher = SpritzHasher::new();
+                for byte in msg.iter() {
+                    hasher.update(&[*byte]);
+                }
+                hasher.finalize_with(&mut digest, LengthEncoding::Extended).unwrap();
+                check_prefix(vector, &digest);
+            }
+            "mac" => {
+                let key = vector.bytes("key");
+                let msg = vector.bytes("msg");
+                let mut digest = vec![0u8; vector.number("length")];
+                if digest.len() <= MAX_DIGEST_SIZE {
+                    SpritzMac::mac(&mut digest, &msg, &key).unwrap();
+                    check_prefix(vector, &digest);
+                } else {
+                    assert!(SpritzMac::mac(&mut digest, &msg, &key).is_err(), "line {}", vector.line);
+                }
+                SpritzMac::mac_with(&mut digest, &msg, &key, LengthEncoding::Extended).unwrap();
+                check_prefix(vector, &digest);
+
+                let mut mac = SpritzMac::new(&key);
+                for chunk in msg.chunks(5) {
+                    mac.update(chunk);
+                }
+                mac.finalize_with(&mut digest, LengthEncoding::Extended).unwrap();
+                check_prefix(vector, &digest);
+            }
+            "random32" => {
+                let mut rng = SpritzRng::new(&vector.bytes("key"));
+                for (n, word) in vector.words("output").iter().enumerate() {
+                    assert_eq!(rng.random32(), *word, "line {}: word {}", vector.line, n);
+                }
+            }
+            "tree" => {
+                let params = TreeParams::new(vector.number("leaf_size"), vector.number("fan_out")).unwrap();
+                let msg = vector.bytes("msg");
+                let mut digest = vec![0u8; vector.number("length")];
+                tree::hash_with(params, &mut digest, &msg, LengthEncoding::Extended).unwrap();
+                check_prefix(vector, &digest);
+
+                let mut hasher = TreeHasher::new(params);
+                for chunk in msg.chunks(1000) {
+                    hasher.update(chunk);
+                }
+                hasher.finalize_with(&mut digest, LengthEncoding::Extended).unwrap();
+                check_prefix(vector, &digest);
+
+                #[cfg(feature = "parallel")]
+                {
+                    tree::hash_parallel_with(params, &mut digest, &msg, LengthEncoding::Extended).unwrap();
+                    check_prefix(vector, &digest);
+                }
+            }
+            "ctr" => {
+                let len = vector.bytes("output").len();
+                let position = vector.text("position").parse().unwrap_or_else(|_| panic!("line {}: bad position", vector.line));
+                let mut ctr = SpritzCtr::new(&vector.bytes("key"), &vector.bytes("nonce"));
+                ctr.seek(position);
+                let mut out = vec![0u8; len];
+                ctr.apply_keystream(&mut out).unwrap();
+                check_prefix(vector, &out);
+
+                let mut out = vec![0u8; len];
+                ctr.apply_keystream_at(position, &mut out).unwrap();
+                check_prefix(vector, &out);
+            }
+            mode => panic!("line {}: unknown mode '{}'", vector.line, mode),
+        }
+    }
+
+    #[test]
+    fn parser() {
+        assert_eq!(parse_value("414243"), Some(vec![0x41, 0x42, 0x43]));
+        assert_eq!(parse_value("\"ABC\""), Some(b"ABC".to_vec()));
+        assert_eq!(parse_value("ff * 3"), Some(vec![0xff; 3]));
+        assert_eq!(parse_value(""), Some(vec![]));
+        assert_eq!(parse_value("4"), None);
+        assert_eq!(parse_value("zz"), None);
+
+        let vectors = parse("# comment\nmode = hash\nmsg =\n\n\nmode = stream\n");
+        assert_eq!(vectors.len(), 2);
+        assert_eq!(vectors[0].text("msg"), "");
+        assert_eq!(vectors[1].line, 6);
+    }
+
+    #[test]
+    fn known_answers() {
+        let vectors = parse(VECTORS);
+        assert!(vectors.len() >= 25);
+
+        for vector in vectors.iter() {
+            run(vector);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod key_schedule {
+    #![allow(deprecated)]
+
+    use spritz_cipher::{SpritzCipher, SpritzCipherContext, SpritzKey, SpritzKeySchedule};
+    use std::mem::MaybeUninit;
+
+    /// Keys around the lengths that shuffle while being absorbed, 64 and 128 bytes.
+    const KEY_SIZES: &[usize] = &[0, 1, 16, 32, 63, 64, 65, 127, 128, 200];
+    const NONCE_SIZES: &[usize] = &[0, 1, 8, 16, 64, 65, 300];
+
+    fn bytes(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|k| (k as u8).wrapping_mul(31).wrapping_add(seed)).collect()
+    }
+
+    #[test]
+    fn matches_setup_with_iv() {
+        for &key_size in KEY_SIZES {
+            let key = bytes(key_size, 1);
+            let schedule = SpritzKeySchedule::new(&key);
+
+            for &nonce_size in NONCE_SIZES {
+                let nonce = bytes(nonce_size, 2);
+                let mut expected = [0u8; 64];
+                let mut context = SpritzCipherContext::setup_with_IV(&key, &nonce);
+                context.crypt(&[0u8; 64], &mut expected).unwrap();
+
+                let mut keystream = [0u8; 64];
+                schedule.cipher(&nonce).apply_keystream(&mut keystream);
+                assert_eq!(keystream, expected, "key {} nonce {}", key_size, nonce_size);
+
+                let mut keystream = [0u8; 64];
+                SpritzCipher::with_nonce(&key, &nonce).apply_keystream(&mut keystream);
+                assert_eq!(keystream, expected, "key {} nonce {}", key_size, nonce_size);
+
+                let mut slot = MaybeUninit::uninit();
+                let mut keystream = [0u8; 64];
+                schedule.cipher_in(&mut slot, &nonce).apply_keystream(&mut keystream);
+                assert_eq!(keystream, expected, "key {} nonce {}", key_size, nonce_size);
+            }
+        }
+    }
+
+    #[test]
+    fn reusable() {
+        let schedule = SpritzKeySchedule::new(b"key");
+        let mut first = *b"packet";
+        let mut second = *b"packet";
+        schedule.cipher(b"1").apply_keystream(&mut first);
+        schedule.cipher(b"2").apply_keystream(&mut second);
+        assert_ne!(first, second);
+
+        schedule.cipher(b"1").apply_keystream(&mut first);
+        assert_eq!(&first, b"packet");
+    }
+
+    #[test]
+    fn typed_key() {
+        let key = SpritzKey::<32>::new([9u8; 32]);
+        let mut a = [0u8; 20];
+        let mut b = [0u8; 20];
+        SpritzKeySchedule::from_key(&key).cipher(b"nonce").apply_keystream(&mut a);
+        SpritzCipher::with_nonce(&[9u8; 32], b"nonce").apply_keystream(&mut b);
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod keystream {
+    use spritz_cipher::{SpritzCipher, SpritzHasher, SpritzRng};
+
+    /// The keystream a byte at a time, which goes through `drip`.
+    fn per_byte(rng: &mut SpritzRng, len: usize) -> Vec<u8> {
+        (0..len).map(|_| rng.random8()).collect()
+    }
+
+    #[test]
+    fn bulk_matches_per_byte() {
+        for len in [0, 1, 2, 255, 256, 257, 4096] {
+            let expected = per_byte(&mut SpritzRng::with_nonce(b"key", b"nonce"), len);
+
+            let mut applied = vec![0u8; len];
+            SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut applied);
+            assert_eq!(applied, expected, "apply_keystream {}", len);
+
+            let mut crypted = vec![0u8; len];
+            SpritzCipher::with_nonce(b"key", b"nonce").crypt(&vec![0u8; len], &mut crypted).unwrap();
+            assert_eq!(crypted, expected, "crypt {}", len);
+
+            let mut filled = vec![0u8; len];
+            SpritzRng::with_nonce(b"key", b"nonce").fill_bytes(&mut filled);
+            assert_eq!(filled, expected, "fill_bytes {}", len);
+        }
+    }
+
+    #[test]
+    fn bulk_and_per_byte_interleave() {
+        let expected = per_byte(&mut SpritzRng::new(b"key"), 2000);
+
+        let mut rng = SpritzRng::new(b"key");
+        let mut out = Vec::new();
+        for (k, chunk) in [0usize, 1, 7, 100, 3, 300, 1].iter().cycle().take(20).enumerate() {
+            if k % 2 == 0 {
+                let mut buf = vec![0u8; *chunk];
+                rng.fill_bytes(&mut buf);
+                out.extend(buf);
+            } else {
+                out.extend(per_byte(&mut rng, *chunk));
+            }
+        }
+        assert_eq!(out, expected[..out.len()]);
+
+        /* Entropy added between calls sets up a shuffle that the bulk path has to run first */
+        let mut a = SpritzRng::new(b"key");
+        let mut b = SpritzRng::new(b"key");
+        let mut bulk = [0u8; 64];
+        a.fill_bytes(&mut bulk[..10]);
+        b.fill_bytes(&mut bulk[..10]);
+        a.add_entropy(b"more");
+        b.add_entropy(b"more");
+        a.fill_bytes(&mut bulk);
+        assert_eq!(bulk.to_vec(), per_byte(&mut b, 64));
+    }
+
+    #[test]
+    fn hash_digest_unchanged() {
+        let mut digest = [0u8; 32];
+        SpritzHasher::hash(&mut digest, b"arcfour").unwrap();
+        assert_eq!(&digest[..4], &[0xff, 0x8c, 0xf2, 0x68]);
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod lanes {
+    use spritz_cipher::lanes::{self, CipherLanes};
+    use spritz_cipher::{LengthEncoding, SpritzCipher, SpritzCipherError, SpritzHasher};
+
+    /// Lengths around the points where absorbing shuffles, 64 and 128 bytes.
+    const LENS: [usize; 8] = [0, 1, 20, 63, 64, 65, 128, 300];
+
+    fn bytes(len: usize, seed: u8) -> Vec<u8> {
+        (0..len).map(|k| (k as u8).wrapping_mul(29).wrapping_add(seed)).collect()
+    }
+
+    #[test]
+    fn ciphers_match_scalar() {
+        let keys: Vec<Vec<u8>> = LENS.iter().map(|&len| bytes(len, 1)).collect();
+        let nonces: Vec<Vec<u8>> = LENS.iter().rev().map(|&len| bytes(len, 2)).collect();
+
+        let mut lanes = CipherLanes::<8>::with_nonces(
+            core::array::from_fn(|l| &keys[l][..]),
+            core::array::from_fn(|l| &nonces[l][..]),
+        );
+        let mut unkeyed = CipherLanes::<8>::new(core::array::from_fn(|l| &keys[l][..]));
+
+        /* Two calls, with different lengths per lane, continue each lane's keystream */
+        let mut first: Vec<Vec<u8>> = LENS.iter().map(|&len| vec![0u8; len]).collect();
+        let mut second: Vec<Vec<u8>> = LENS.iter().rev().map(|&len| vec![0u8; len]).collect();
+        {
+            let mut first = first.iter_mut();
+            lanes.apply_keystream(core::array::from_fn(|_| &mut first.next().unwrap()[..]));
+            let mut second = second.iter_mut();
+            lanes.apply_keystream(core::array::from_fn(|_| &mut second.next().unwrap()[..]));
+        }
+        let mut plain: Vec<Vec<u8>> = LENS.iter().map(|_| vec![0u8; 100]).collect();
+        {
+            let mut plain = plain.iter_mut();
+            unkeyed.apply_keystream(core::array::from_fn(|_| &mut plain.next().unwrap()[..]));
+        }
+
+        for l in 0..8 {
+            let mut cipher = SpritzCipher::with_nonce(&keys[l], &nonces[l]);
+            let mut expected = vec![0u8; first[l].len() + second[l].len()];
+            cipher.apply_keystream(&mut expected);
+            assert_eq!([&first[l][..], &second[l][..]].concat(), expected, "lane {}", l);
+
+            let mut expected = vec![0u8; 100];
+            SpritzCipher::new(&keys[l]).apply_keystream(&mut expected);
+            assert_eq!(plain[l], expected, "unkeyed lane {}", l);
+        }
+    }
+
+    #[test]
+    fn hashes_match_scalar() {
+        let data: Vec<Vec<u8>> = LENS.iter().map(|&len| bytes(len, 3)).collect();
+        let sizes = [1usize, 32, 255, 32, 64, 0, 16, 100];
+
+        let mut digests: Vec<Vec<u8>> = sizes.iter().map(|&size| vec![0u8; size]).collect();
+        {
+            let mut digests = digests.iter_mut();
+            lanes::hash::<8>(core::array::from_fn(|_| &mut digests.next().unwrap()[..]), core::array::from_fn(|l| &data[l][..]))
+                .unwrap();
+        }
+        for l in 0..8 {
+            let mut expected = vec![0u8; sizes[l]];
+            SpritzHasher::hash(&mut expected, &data[l]).unwrap();
+            assert_eq!(digests[l], expected, "lane {}", l);
+        }
+    }
+
+    #[test]
+    fn equal_lengths_match_scalar() {
+        /* Every lane shuffles at once, which is where the `avx2` lanes whip, in two groups */
+        let keys: Vec<Vec<u8>> = (0..16).map(|l| bytes(32, l)).collect();
+        let mut out = vec![vec![0u8; 200]; 16];
+        {
+            let mut lanes = CipherLanes::<16>::with_nonces(core::array::from_fn(|l| &keys[l][..]), [&b"nonce"[..]; 16]);
+            let mut out = out.iter_mut();
+            lanes.apply_keystream(core::array::from_fn(|_| &mut out.next().unwrap()[..]));
+        }
+        for l in 0..16 {
+            let mut expected = vec![0u8; 200];
+            SpritzCipher::with_nonce(&keys[l], b"nonce").apply_keystream(&mut expected);
+            assert_eq!(out[l], expected, "lane {}", l);
+        }
+    }
+
+    #[test]
+    fn long_digests() {
+        let sizes = [256usize, 32, 300, 65536 + 7];
+        let mut digests: Vec<Vec<u8>> = sizes.iter().map(|&size| vec![0u8; size]).collect();
+        {
+            let mut digests = digests.iter_mut();
+            lanes::hash_with::<4>(
+                core::array::from_fn(|_| &mut digests.next().unwrap()[..]),
+                [b"a", b"b", b"", b"long"],
+                LengthEncoding::Extended,
+            )
+            .unwrap();
+        }
+        for (l, msg) in [&b"a"[..], b"b", b"", b"long"].iter().enumerate() {
+            let mut expected = vec![0u8; sizes[l]];
+            SpritzHasher::hash_with(&mut expected, msg, LengthEncoding::Extended).unwrap();
+            assert_eq!(digests[l], expected, "lane {}", l);
+        }
+
+        let mut short = [0u8; 32];
+        let mut long = [0u8; 256];
+        assert_eq!(
+            lanes::hash([&mut short, &mut long], [b"", b""]),
+            Err(SpritzCipherError::DigestTooLong { max: 255, actual: 256 })
+        );
+        assert_eq!(short, [0u8; 32]);
+    }
+
+    #[test]
+    fn one_lane() {
+        let mut data = *b"only lane";
+        CipherLanes::with_nonces([b"key"], [b"nonce"]).apply_keystream([&mut data]);
+        let mut expected = *b"only lane";
+        SpritzCipher::with_nonce(b"key", b"nonce").apply_keystream(&mut expected);
+        assert_eq!(data, expected);
+    }
+}
@@ -0,0 +1,125 @@
+//! Every public function must return (or report an error) for any input, in debug builds
+//! too where integer overflow panics.
+
+#[cfg(test)]
+mod panic_free {
+    use spritz_cipher::{SpritzCipher, SpritzCipherError, SpritzHasher, SpritzMac, SpritzRng, MAX_DIGEST_SIZE, MIN_TAG_SIZE};
+    use spritz_cipher::{ct, stats};
+
+    #[test]
+    fn many_whips() {
+        /* Every shuffle adds 3 * 2 to w, more than 42 shuffles used to overflow it */
+        let data = vec![0x5a; 64 * 100];
+
+        let mut digest = [0u8; 32];
+        SpritzHasher::hash(&mut digest, &data).unwrap();
+
+        let mut rng = SpritzRng::new(&data);
+        rng.add_entropy(&data);
+        rng.random32();
+
+        let mut cipher = SpritzCipher::with_nonce(&data, &data);
+        let mut out = vec![0u8; data.len()];
+        cipher.crypt(&data, &mut out).unwrap();
+    }
+
+    #[test]
+    fn random32_uniform_bounds() {
+        let mut rng = SpritzRng::new(b"bounds");
+        assert_eq!(rng.random32_uniform(0), 0);
+        assert_eq!(rng.random32_uniform(1), 0);
+        for bound in [2u32, 3, 255, 256, 1 << 31, (1 << 31) + 1, u32::MAX - 1, u32::MAX].iter() {
+            for _ in 0..10 {
+                assert!(rng.random32_uniform(*bound) < *bound);
+            }
+        }
+    }
+
+    #[test]
+    fn digest_lengths() {
+        for len in [0usize, 1, MIN_TAG_SIZE - 1, MIN_TAG_SIZE, 32, MAX_DIGEST_SIZE].iter() {
+            let mut digest = vec![0u8; *len];
+            SpritzHasher::hash(&mut digest, b"msg").unwrap();
+            SpritzMac::mac(&mut digest, &[1, 2, 3], &[4, 5, 6]).unwrap();
+            let mut mac = SpritzMac::new(&[4, 5, 6]);
+            mac.update(&[1, 2, 3]);
+            /* A tag too short to be safe never verifies, an empty one least of all */
+            assert_eq!(mac.verify(&digest), *len >= MIN_TAG_SIZE, "{}", len);
+        }
+
+        /* Stripping the tag doesn't get past the in-place MAC either */
+        let mut slot = std::mem::MaybeUninit::uninit();
+        let mut mac = SpritzMac::new_in(&mut slot, &[4, 5, 6]);
+        mac.update(&[1, 2, 3]);
+        assert!(!mac.verify(&[]));
+
+        for len in [MAX_DIGEST_SIZE + 1, 300, 4096].iter() {
+            let mut digest = vec![0u8; *len];
+            assert!(matches!(SpritzHasher::hash(&mut digest, b"msg"), Err(SpritzCipherError::DigestTooLong { .. })));
+            assert!(matches!(SpritzMac::mac(&mut digest, &[1], &[2]), Err(SpritzCipherError::DigestTooLong { .. })));
+            assert!(!SpritzMac::new(&[2]).verify(&digest));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_context() {
+        use spritz_cipher::SpritzCipherContext;
+
+        /* A failed final leaves the context usable */
+        let mut digest = [0u8; MAX_DIGEST_SIZE + 1];
+        let mut context = SpritzCipherContext::hash_setup();
+        context.hash_update(b"msg");
+        assert!(context.hash_final(&mut digest).is_err());
+        let mut short = [0u8; 32];
+        context.hash_final(&mut short).unwrap();
+        let mut expected = [0u8; 32];
+        SpritzHasher::hash(&mut expected, b"msg").unwrap();
+        assert_eq!(short, expected);
+
+        assert!(SpritzCipherContext::compare(&[1], &[]).is_err());
+        assert_eq!(SpritzCipherContext::compare(&[], &[]).unwrap(), 0);
+        assert!(SpritzCipherContext::setup(&[]).crypt(&[1, 2], &mut [0]).is_err());
+    }
+
+    #[test]
+    fn empty_and_mismatched() {
+        let mut cipher = SpritzCipher::new(&[]);
+        cipher.crypt(&[], &mut []).unwrap();
+        cipher.apply_keystream(&mut []);
+        assert!(cipher.crypt(&[1, 2], &mut [0]).is_err());
+
+        let mut rng = SpritzRng::with_nonce(&[], &[]);
+        rng.add_entropy(&[]);
+        rng.fill_bytes(&mut []);
+
+        let mut mac = SpritzMac::new(&[]);
+        mac.update(&[]);
+        mac.finalize(&mut []).unwrap();
+
+        SpritzHasher::new().finalize(&mut []).unwrap();
+
+        assert!(ct::ct_eq(&[], &[]));
+        assert!(!ct::ct_eq_length_hiding(&[], &[1]));
+        assert!(ct::conditional_copy(&mut [], &[1], ct::Choice::from(1)).is_err());
+    }
+
+    #[test]
+    fn stats_edge_cases() {
+        for data in [&[][..], &[0u8][..], &[0xffu8; 3][..]].iter() {
+            stats::run_all(data);
+            for m in [0u32, 1, 2, 3, 64, u32::MAX].iter() {
+                stats::serial(data, *m);
+            }
+            for shift in [0usize, 1, 100, usize::MAX].iter() {
+                stats::autocorrelation(data, *shift);
+            }
+        }
+
+        assert_eq!(stats::erfc(0.0), 1.0);
+        stats::igamc(0.0, 0.0);
+        stats::igamc(1e9, 1e-9);
+        stats::erfc(f64::INFINITY);
+        stats::erfc(f64::NAN);
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod secret {
+    use spritz_cipher::Secret;
+    use std::panic::{self, AssertUnwindSafe};
+
+    const TOKEN: &[u8] = b"an API token kept for hours";
+
+    /// What a `Vec` secret's buffer holds right now, as a heap dump would show it.
+    fn buffer(ptr: *const u8) -> Vec<u8> {
+        unsafe { std::slice::from_raw_parts(ptr, TOKEN.len()) }.to_vec()
+    }
+
+    #[test]
+    fn round_trip() {
+        let secret = Secret::new(TOKEN.to_vec());
+        assert!(secret.expose(|token| token == TOKEN));
+
+        let array = Secret::new(*b"0123456789abcdef");
+        assert_eq!(array.expose(|key| *key), *b"0123456789abcdef");
+        assert_eq!(format!("{:?}", array), "Secret(..)");
+    }
+
+    #[test]
+    fn encrypted_in_its_buffer() {
+        let token = TOKEN.to_vec();
+        let ptr = token.as_ptr();
+        let secret = Secret::new(token);
+
+        let sealed = buffer(ptr);
+        assert_ne!(sealed, TOKEN);
+        secret.expose(|token| assert_eq!(token, TOKEN));
+        assert_eq!(buffer(ptr), sealed);
+    }
+
+    #[test]
+    fn fresh_nonce_per_seal() {
+        let token = TOKEN.to_vec();
+        let ptr = token.as_ptr();
+        let mut secret = Secret::new(token);
+
+        let first = buffer(ptr);
+        secret.expose_mut(|_| ());
+        let second = buffer(ptr);
+        assert_ne!(first, second);
+        assert_ne!(second, TOKEN);
+
+        /* The same value sealed twice doesn't look the same either */
+        let token = TOKEN.to_vec();
+        let other_ptr = token.as_ptr();
+        let other = Secret::new(token);
+        assert_ne!(buffer(other_ptr), first);
+        assert_ne!(buffer(other_ptr), second);
+        other.expose(|token| assert_eq!(token, TOKEN));
+        secret.expose(|token| assert_eq!(token, TOKEN));
+    }
+
+    #[test]
+    fn expose_mut_changes_value() {
+        let mut secret = Secret::new(*b"old key");
+        secret.expose_mut(|key| key.copy_from_slice(b"new key"));
+        assert_eq!(secret.expose(|key| *key), *b"new key");
+    }
+
+    #[test]
+    fn resealed_after_panic() {
+        let token = TOKEN.to_vec();
+        let ptr = token.as_ptr();
+        let mut secret = Secret::new(token);
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| secret.expose_mut(|_| panic!("oops"))));
+        assert!(result.is_err());
+        assert_ne!(buffer(ptr), TOKEN);
+        secret.expose(|token| assert_eq!(token, TOKEN));
+    }
+}
@@ -0,0 +1,125 @@
+//! Locked memory, checked against what the kernel reports in `/proc/self/smaps`.
+//!
+//! Each box locks a page, well under the usual `ulimit -l` of 64 KB or more.
+
+#[cfg(all(test, target_os = "linux"))]
+mod secure_box {
+    #![allow(deprecated)]
+
+    use spritz_cipher::mem::SecureBox;
+    use spritz_cipher::{SpritzCipher, SpritzCipherContext, SpritzRng};
+    use std::fs;
+    use std::mem::MaybeUninit;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{Command, Stdio};
+
+    /// The permissions and `VmFlags` of the mapping holding `addr`.
+    fn mapping(addr: usize) -> Option<(String, Vec<String>)> {
+        let smaps = fs::read_to_string("/proc/self/smaps").unwrap();
+        let mut found: Option<String> = None;
+        for line in smaps.lines() {
+            let mut fields = line.split_whitespace();
+            let first = fields.next().unwrap_or("");
+            if let Some((start, end)) = first.split_once('-') {
+                if let (Ok(start), Ok(end)) = (usize::from_str_radix(start, 16), usize::from_str_radix(end, 16)) {
+                    found = if (start..end).contains(&addr) { fields.next().map(String::from) } else { None };
+                    continue;
+                }
+            }
+            if first == "VmFlags:" {
+                if let Some(perms) = found.take() {
+                    return Some((perms, fields.map(String::from).collect()));
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn locked_undumpable_and_guarded() {
+        let key = SecureBox::new([0u8; 32]).unwrap();
+        let start = key.as_ptr() as usize;
+        let end = start + key.len();
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+
+        let (perms, flags) = mapping(start).unwrap();
+        assert_eq!(perms, "rw-p");
+        assert!(flags.iter().any(|f| f == "lo"), "not locked: {:?}", flags);
+        assert!(flags.iter().any(|f| f == "dd"), "dumpable: {:?}", flags);
+
+        /* The key ends on a page boundary, followed by the guard page */
+        assert_eq!(end % page, 0);
+        assert_eq!(mapping(end).unwrap().0, "---p");
+        assert_eq!(mapping(start - page).unwrap().0, "---p");
+    }
+
+    #[test]
+    fn unmapped_on_drop() {
+        let key = SecureBox::new([1u8; 32]).unwrap();
+        let addr = key.as_ptr() as usize;
+        drop(key);
+        assert!(mapping(addr).is_none_or(|(_, flags)| !flags.iter().any(|f| f == "lo")));
+    }
+
+    #[test]
+    fn cipher_in_place() {
+        let mut key = SecureBox::new([0u8; 32]).unwrap();
+        SpritzRng::new(b"seed").fill_bytes(&mut *key);
+
+        let mut boxed = SecureBox::new_with(|slot| SpritzCipher::with_nonce_in(slot, &*key, b"nonce")).unwrap();
+        let mut by_value = SpritzCipher::with_nonce(&*key, b"nonce");
+
+        let mut a = [7u8; 100];
+        let mut b = [7u8; 100];
+        boxed.apply_keystream(&mut a);
+        by_value.apply_keystream(&mut b);
+        assert_eq!(a, b);
+        assert_eq!(format!("{:?}", boxed), "SecureBox(..)");
+    }
+
+    #[test]
+    fn other_slot_is_an_error() {
+        let other: &'static mut MaybeUninit<SpritzRng> = Box::leak(Box::new(MaybeUninit::uninit()));
+        assert!(SecureBox::new_with(move |_| SpritzRng::new_in(other, b"seed")).is_err());
+    }
+
+    #[test]
+    fn deprecated_context() {
+        let mut boxed = SecureBox::<SpritzCipherContext>::setup_with_IV(b"key", b"nonce").unwrap();
+        let mut by_value = SpritzCipherContext::setup_with_IV(b"key", b"nonce");
+        assert_eq!(boxed.random32(), by_value.random32());
+
+        let mut boxed = SecureBox::<SpritzCipherContext>::setup(b"key").unwrap();
+        let mut by_value = SpritzCipherContext::setup(b"key");
+        assert_eq!(boxed.random32(), by_value.random32());
+    }
+
+    #[test]
+    fn large_and_zero_sized() {
+        let mut big = SecureBox::new([0u8; 10000]).unwrap();
+        big[9999] = 1;
+        assert_eq!(big.iter().map(|&b| b as u32).sum::<u32>(), 1);
+        SecureBox::new(()).unwrap();
+    }
+
+    /// Writes one byte past a box, only run by `overrun_faults`.
+    #[test]
+    #[ignore]
+    fn overrun_child() {
+        if std::env::var_os("SPRITZ_OVERRUN_CHILD").is_some() {
+            let mut key = SecureBox::new([0u8; 32]).unwrap();
+            unsafe { key.as_mut_ptr().add(32).write_volatile(1) };
+        }
+    }
+
+    #[test]
+    fn overrun_faults() {
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["secure_box::overrun_child", "--exact", "--ignored", "--test-threads=1"])
+            .env("SPRITZ_OVERRUN_CHILD", "1")
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGSEGV));
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod spritz_rng {
+    use spritz_cipher::{SpritzCipher, SpritzRng};
+    use std::process::Command;
+
+    fn spritz_rng(args: &[&str]) -> Vec<u8> {
+        let out = Command::new(env!("CARGO_BIN_EXE_spritz-rng")).args(args).output().unwrap();
+        assert!(out.status.success());
+        out.stdout
+    }
+
+    #[test]
+    fn keystream() {
+        const COUNT: usize = 1000;
+
+        /* XOR-ing zeros gives the raw keystream */
+        let zeros = [0u8; COUNT + 10];
+        let mut expected = [0u8; COUNT + 10];
+        let mut cipher = SpritzCipher::new(b"1234");
+        cipher.crypt(&zeros, &mut expected).unwrap();
+
+        assert_eq!(spritz_rng(&["--seed", "1234", "--count", "1000"]), &expected[..COUNT]);
+        assert_eq!(spritz_rng(&["-k", "31323334", "-c", "1000"]), &expected[..COUNT]);
+        assert_eq!(spritz_rng(&["-s", "1234", "-c", "1000", "--drop", "10"]), &expected[10..]);
+
+        let mut expected = [0u8; COUNT];
+        let mut cipher = SpritzCipher::with_nonce(b"1234", &[0xaa, 0xbb]);
+        cipher.crypt(&zeros[..COUNT], &mut expected).unwrap();
+        assert_eq!(spritz_rng(&["-s", "1234", "-n", "aabb", "-c", "1000"]), &expected[..]);
+
+        /* A drop longer than the output buffer */
+        let mut rng = SpritzRng::new(b"1234");
+        let mut expected = vec![0u8; 100_000 + COUNT];
+        rng.fill_bytes(&mut expected);
+        assert_eq!(spritz_rng(&["-s", "1234", "-c", "1000", "-d", "100000"]), &expected[100_000..]);
+    }
+
+    #[test]
+    fn words() {
+        let out = spritz_rng(&["--seed", "1234", "--words", "--count", "400"]);
+        assert_eq!(out.len(), 400);
+
+        let mut rng = SpritzRng::new(b"1234");
+        for word in out.chunks(4) {
+            assert_eq!(word, rng.random32().to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn words_rate_limited() {
+        /* The rate splits the output into buffers that aren't whole words */
+        let out = spritz_rng(&["--seed", "1234", "--words", "--count", "1003", "--rate", "1001"]);
+        assert_eq!(out.len(), 1003);
+
+        let mut rng = SpritzRng::new(b"1234");
+        for word in out.chunks(4) {
+            assert_eq!(word, &rng.random32().to_le_bytes()[..word.len()]);
+        }
+    }
+
+    #[test]
+    fn rate_limit() {
+        let start = std::time::Instant::now();
+        let out = spritz_rng(&["--seed", "1234", "--count", "3000", "--rate", "2000"]);
+        assert_eq!(out.len(), 3000);
+        assert!(start.elapsed().as_millis() >= 1000);
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod spritzsum {
+    use std::fs;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process::{Command, Output, Stdio};
+
+    fn spritzsum(args: &[&str], stdin: &[u8]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_spritzsum"))
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(stdin).unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spritzsum-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn hash_stdin() {
+        /* Same vector as the hash doctest */
+        let out = spritzsum(&[], b"arcfour");
+        assert!(out.status.success());
+        assert_eq!(
+            String::from_utf8(out.stdout).unwrap(),
+            "ff8cf268094c87b95f74ce6fee9d3003a5f9fe6944653cd50e66bf189c63f699  -\n"
+        );
+
+        let out = spritzsum(&["--tag", "-l", "4"], b"arcfour");
+        assert!(out.status.success());
+        assert!(String::from_utf8(out.stdout).unwrap().starts_with("SPRITZ-32 (-) = "));
+    }
+
+    #[test]
+    fn keyed() {
+        /* Same vector as the MAC doctest */
+        let out = spritzsum(&["-k", "000102"], b"ABC");
+        assert!(out.status.success());
+        assert_eq!(
+            String::from_utf8(out.stdout).unwrap(),
+            "be8edcf276cf57b40ebc8e2243457e3eb7c64d4e991e9358ce81efb16ccec7ed  -\n"
+        );
+    }
+
+    #[test]
+    fn check_round_trip() {
+        let dir = scratch_dir("check");
+        let data = dir.join("data");
+        fs::create_dir_all(data.join("sub")).unwrap();
+        fs::write(data.join("a.txt"), b"hello").unwrap();
+        fs::write(data.join("sub/b.txt"), b"world").unwrap();
+        let dir_name = data.to_str().unwrap();
+
+        /* Directories need -r */
+        assert!(!spritzsum(&[dir_name], b"").status.success());
+
+        for args in [vec!["-r", dir_name], vec!["-r", "--tag", "-l", "16", dir_name]].iter() {
+            let out = spritzsum(args, b"");
+            assert!(out.status.success());
+            let sums = String::from_utf8(out.stdout).unwrap();
+            assert_eq!(sums.lines().count(), 2);
+            fs::write(dir.join("SUMS"), &sums).unwrap();
+
+            let out = spritzsum(&["-c", dir.join("SUMS").to_str().unwrap()], b"");
+            assert!(out.status.success());
+            assert_eq!(String::from_utf8(out.stdout).unwrap().matches(": OK").count(), 2);
+
+            /* Modify a file, the check must now fail */
+            fs::write(data.join("a.txt"), b"hellO").unwrap();
+            let out = spritzsum(&["-c", dir.join("SUMS").to_str().unwrap()], b"");
+            assert!(!out.status.success());
+            assert_eq!(String::from_utf8(out.stdout).unwrap().matches(": FAILED").count(), 1);
+            fs::write(data.join("a.txt"), b"hello").unwrap();
+        }
+
+        /* A keyed checksum list doesn't verify without the key */
+        let out = spritzsum(&["-r", "--tag", "-k", "aabb", dir_name], b"");
+        fs::write(dir.join("SUMS"), &out.stdout).unwrap();
+        assert!(spritzsum(&["-c", "-k", "aabb", dir.join("SUMS").to_str().unwrap()], b"").status.success());
+        assert!(!spritzsum(&["-c", dir.join("SUMS").to_str().unwrap()], b"").status.success());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn check_bad_tagged_lines() {
+        let dir = scratch_dir("tagged");
+        let file = dir.join("f");
+        fs::write(&file, b"data").unwrap();
+        let name = file.to_str().unwrap();
+
+        /* Too long for a digest, empty, and without a file name */
+        let lines = [
+            format!("SPRITZ-2048 ({}) = {}\n", name, "ab".repeat(256)),
+            format!("SPRITZ-0 ({}) = \n", name),
+            "SPRITZ-8 () = ab\n".to_string(),
+        ];
+        for line in lines.iter() {
+            let out = spritzsum(&["-c", "-"], line.as_bytes());
+            assert_eq!(out.status.code(), Some(1), "{}", line);
+            assert!(!String::from_utf8(out.stdout).unwrap().contains("OK"), "{}", line);
+            assert!(String::from_utf8(out.stderr).unwrap().contains("improperly formatted"), "{}", line);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
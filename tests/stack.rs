@@ -0,0 +1,140 @@
+//! Looks for S-box copies left in dead stack frames.
+//!
+//! Reading below the stack pointer isn't something Rust promises anything about, but it is
+//! mapped memory on every platform we test on and it's the only way to see what is left there.
+
+#[cfg(test)]
+mod stack {
+    use spritz_cipher::mem::scrub_stack;
+    use spritz_cipher::{SpritzCipher, SpritzHasher, SpritzMac, SpritzRng};
+    use std::hint::black_box;
+    use std::mem::MaybeUninit;
+
+    /// How far below the test's frame to look.
+    const SCAN: usize = 32 * 1024;
+    /// The top of the scanned area, overwritten by the scanner's own calls.
+    const SKIP: usize = 1024;
+    const PAD: usize = 2 * SKIP;
+
+    /// Run `f` with `PAD` bytes of stack in use above it, so what it leaves behind is out of
+    /// reach of the scanner's own frames.
+    #[inline(never)]
+    fn deep(f: fn()) {
+        let pad = [0u8; PAD];
+        black_box(&pad);
+        f();
+    }
+
+    /// Whether the dead stack below `top` holds a scrambled 256 byte permutation, which is what
+    /// a keyed S-box looks like.
+    ///
+    /// Copies of the unkeyed state (the identity) are everywhere and harmless, read one byte in
+    /// and followed by `i = 0` they are a permutation too. They are mostly runs of consecutive
+    /// values, a keyed S-box has hardly any.
+    #[inline(never)]
+    fn find_sbox(top: *const u8) -> bool {
+        let low = top as usize - SCAN;
+        let high = top as usize - SKIP - 256;
+        for start in low..high {
+            let mut seen = [0u64; 4];
+            let mut runs = 0;
+            let mut previous = 0u8;
+            for k in 0..256 {
+                let byte = unsafe { std::ptr::read_volatile((start + k) as *const u8) };
+                seen[(byte / 64) as usize] |= 1 << (byte % 64);
+                if k > 0 && byte == previous.wrapping_add(1) {
+                    runs += 1;
+                }
+                previous = byte;
+            }
+            if runs < 128 && seen.iter().all(|&bits| bits == u64::MAX) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// A permutation left on the stack, to check the scanner finds one.
+    #[inline(never)]
+    fn plant() {
+        let mut sbox = [0u8; 256];
+        for (k, entry) in sbox.iter_mut().enumerate() {
+            *entry = (k as u8).wrapping_mul(167).wrapping_add(13);
+        }
+        black_box(&sbox);
+    }
+
+    #[inline(never)]
+    fn in_place() {
+        let mut slot = MaybeUninit::uninit();
+        let mut cipher = SpritzCipher::new_in(&mut slot, b"stack key");
+        let mut msg = *b"stack message";
+        cipher.apply_keystream(&mut msg);
+        black_box(&msg);
+
+        let mut slot = MaybeUninit::uninit();
+        let mut rng = SpritzRng::with_nonce_in(&mut slot, b"stack key", b"nonce");
+        black_box(rng.random32());
+
+        let mut slot = MaybeUninit::uninit();
+        let mut mac = SpritzMac::new_in(&mut slot, b"stack key");
+        mac.update(b"stack message");
+        let mut tag = [0u8; 32];
+        mac.finalize(&mut tag).unwrap();
+
+        let mut slot = MaybeUninit::uninit();
+        let mut hasher = SpritzHasher::new_in(&mut slot);
+        hasher.update(b"stack secret");
+        hasher.finalize(&mut tag).unwrap();
+        black_box(&tag);
+    }
+
+    #[inline(never)]
+    fn boxed() {
+        let mut cipher = SpritzCipher::with_nonce_boxed(b"stack key", b"nonce");
+        let mut msg = *b"stack message";
+        cipher.apply_keystream(&mut msg);
+        black_box(&msg);
+
+        let mut rng = SpritzRng::new_boxed(b"stack key");
+        black_box(rng.random8());
+    }
+
+    #[test]
+    fn scanner_finds_planted_sbox() {
+        let top = 0u8;
+        deep(plant);
+        assert!(find_sbox(&top));
+
+        scrub_stack::<SCAN>();
+        assert!(!find_sbox(&top));
+    }
+
+    #[test]
+    fn in_place_leaves_nothing() {
+        let top = 0u8;
+        deep(in_place);
+        assert!(!find_sbox(&top));
+    }
+
+    #[test]
+    fn boxed_leaves_nothing() {
+        let top = 0u8;
+        deep(boxed);
+        assert!(!find_sbox(&top));
+    }
+
+    #[test]
+    fn scrub_after_by_value() {
+        let top = 0u8;
+        deep(|| {
+            let mut cipher = SpritzCipher::new(b"stack key");
+            let mut msg = *b"stack message";
+            cipher.apply_keystream(&mut msg);
+            black_box(&msg);
+        });
+        /* Whether a copy is left depends on the optimiser, after scrubbing there is none */
+        scrub_stack::<SCAN>();
+        assert!(!find_sbox(&top));
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod stats {
+    use spritz_cipher::SpritzRng;
+    use spritz_cipher::stats::{self, DEFAULT_ALPHA, DEFAULT_SAMPLE_SIZE};
+
+    fn assert_passes(sample: &[u8]) {
+        let results = stats::run_all(sample);
+        assert_eq!(results.len(), 7);
+        for result in results.iter() {
+            println!("{}", result);
+            assert!(result.passed(DEFAULT_ALPHA), "{}", result);
+        }
+    }
+
+    #[test]
+    fn keystream_passes() {
+        for key in [&b"ABC"[..], b"spam", b"arcfour"].iter() {
+            let mut rng = SpritzRng::new(key);
+            assert_passes(&stats::keystream(&mut rng, DEFAULT_SAMPLE_SIZE));
+        }
+
+        let mut rng = SpritzRng::with_nonce(b"key", b"nonce");
+        assert_passes(&stats::keystream(&mut rng, DEFAULT_SAMPLE_SIZE));
+    }
+
+    #[test]
+    fn random32_passes() {
+        let mut rng = SpritzRng::new(b"random32");
+        assert_passes(&stats::random32_stream(&mut rng, DEFAULT_SAMPLE_SIZE / 4));
+    }
+
+    #[test]
+    fn bad_input_fails() {
+        let zeros = [0u8; 4096];
+        for result in stats::run_all(&zeros).iter() {
+            assert!(!result.passed(DEFAULT_ALPHA), "{}", result);
+        }
+
+        /* Balanced bits but far too regular */
+        let pattern: Vec<u8> = (0..4096).map(|i| if i % 2 == 0 { 0x55 } else { 0xaa }).collect();
+        assert!(stats::monobit(&pattern).passed(DEFAULT_ALPHA));
+        assert!(!stats::runs(&pattern).passed(DEFAULT_ALPHA));
+        assert!(!stats::byte_frequency(&pattern).passed(DEFAULT_ALPHA));
+        assert!(!stats::autocorrelation(&pattern, 1).passed(DEFAULT_ALPHA));
+    }
+
+    #[test]
+    fn special_functions() {
+        /* Reference values from the NIST SP 800-22 worked examples */
+        assert!((stats::erfc(0.632455532 / std::f64::consts::SQRT_2) - 0.527089).abs() < 1e-6);
+        assert!((stats::igamc(3.0 / 2.0, 4.882605 / 2.0) - 0.180598).abs() < 1e-6);
+        assert!((stats::igamc(2.0, 1.6 / 2.0) - 0.808792).abs() < 1e-6);
+    }
+
+    #[test]
+    fn welch_t_test() {
+        let mut same = stats::WelchTTest::new();
+        let mut different = stats::WelchTTest::new();
+        assert_eq!(same.t(), 0.0);
+
+        for n in 0..1000 {
+            let noise = ((n / 2) % 10) as f64;
+            same.push(n % 2, 100.0 + noise);
+            different.push(n % 2, if n % 2 == 0 { 100.0 } else { 110.0 } + noise);
+        }
+
+        assert_eq!(same.counts(), [500, 500]);
+        assert!(same.t().abs() < 1.0);
+        assert!(different.t() < -50.0);
+        assert!((different.means()[0] - 104.5).abs() < 1e-9);
+        assert!((different.means()[1] - 114.5).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,264 @@
+#[cfg(test)]
+mod stream {
+    use spritz_cipher::stream::{StreamDecryptor, StreamEncryptor, StreamReader, StreamWriter, CHUNK_LEN, TAG_LEN};
+    use spritz_cipher::{SpritzCipherError, Tag};
+    use std::io::{self, Read, Write};
+
+    const FRAME: usize = CHUNK_LEN + TAG_LEN;
+
+    fn plain(len: usize) -> Vec<u8> {
+        (0..len).map(|n| (n * 31 + n / 251) as u8).collect()
+    }
+
+    fn encrypt(data: &[u8], piece: usize) -> Vec<u8> {
+        let mut writer = StreamWriter::new(Vec::new(), b"key", b"nonce");
+        for chunk in data.chunks(piece.max(1)) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+
+    fn decrypt(stored: &[u8], key: &[u8], nonce: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        StreamReader::new(stored, key, nonce).read_to_end(&mut out)?;
+        Ok(out)
+    }
+
+    fn assert_rejected(stored: &[u8]) {
+        let err = decrypt(stored, b"key", b"nonce").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.into_inner().unwrap().downcast::<SpritzCipherError>().unwrap().as_ref(), &SpritzCipherError::AuthenticationFailed);
+    }
+
+    #[test]
+    fn chunks_round_trip() {
+        let mut chunks = [plain(100), plain(0), plain(37)];
+        let mut encryptor = StreamEncryptor::new(b"key", b"nonce");
+        let first = encryptor.encrypt_next(&mut chunks[0]).unwrap();
+        let second = encryptor.encrypt_next(&mut chunks[1]).unwrap();
+        let last = encryptor.encrypt_last(&mut chunks[2]);
+        assert!(chunks[0] != plain(100));
+
+        let mut decryptor = StreamDecryptor::new(b"key", b"nonce");
+        decryptor.decrypt_next(&mut chunks[0], &first).unwrap();
+        decryptor.decrypt_next(&mut chunks[1], &second).unwrap();
+        decryptor.decrypt_last(&mut chunks[2], &last).unwrap();
+        assert_eq!(chunks, [plain(100), plain(0), plain(37)]);
+    }
+
+    #[test]
+    fn chunks_bound_to_position() {
+        let mut chunks = [plain(50), plain(50)];
+        let mut encryptor = StreamEncryptor::new(b"key", b"nonce");
+        let first = encryptor.encrypt_next(&mut chunks[0]).unwrap();
+        let last = encryptor.encrypt_last(&mut chunks[1]);
+        let sealed = chunks.clone();
+
+        /* Swapped chunks, a chunk given as the wrong kind, and a stream ending early all fail */
+        let mut decryptor = StreamDecryptor::new(b"key", b"nonce");
+        assert_eq!(decryptor.decrypt_next(&mut chunks[1], &last), Err(SpritzCipherError::AuthenticationFailed));
+        assert_eq!(StreamDecryptor::new(b"key", b"nonce").decrypt_last(&mut chunks[0], &first), Err(SpritzCipherError::AuthenticationFailed));
+        assert!(chunks == sealed);
+
+        /* A failed chunk leaves the position alone */
+        decryptor.decrypt_next(&mut chunks[0], &first).unwrap();
+        assert!(decryptor.decrypt_next(&mut chunks[1], &last).is_err());
+        decryptor.decrypt_last(&mut chunks[1], &last).unwrap();
+        assert_eq!(chunks, [plain(50), plain(50)]);
+    }
+
+    #[test]
+    fn failed_chunk_unchanged() {
+        let mut chunk = plain(100);
+        let tag = StreamEncryptor::new(b"key", b"nonce").encrypt_last(&mut chunk);
+        let mut bytes = *tag.as_bytes();
+        bytes[0] ^= 1;
+        let sealed = chunk.clone();
+
+        let bad = Tag::new(bytes);
+        assert!(StreamDecryptor::new(b"key", b"nonce").decrypt_last(&mut chunk, &bad).is_err());
+        assert!(StreamDecryptor::new(b"key", b"other").decrypt_last(&mut chunk, &tag).is_err());
+        assert!(chunk == sealed);
+    }
+
+    #[test]
+    fn io_round_trip() {
+        for &len in [0, 1, CHUNK_LEN - 1, CHUNK_LEN, CHUNK_LEN + 1, 3 * CHUNK_LEN].iter() {
+            let data = plain(len);
+            let stored = encrypt(&data, len);
+            let chunks = len.saturating_sub(1) / CHUNK_LEN + 1;
+            assert_eq!(stored.len(), len + chunks * TAG_LEN, "{}", len);
+            assert!(decrypt(&stored, b"key", b"nonce").unwrap() == data, "{}", len);
+        }
+    }
+
+    #[test]
+    fn io_pieces() {
+        let data = plain(2 * CHUNK_LEN + 500);
+        let stored = encrypt(&data, data.len());
+        for &piece in [1, 7, 1000, CHUNK_LEN + 3].iter() {
+            assert!(encrypt(&data, piece) == stored, "{}", piece);
+
+            let mut reader = StreamReader::new(&stored[..], b"key", b"nonce");
+            let mut out = Vec::new();
+            let mut buf = vec![0u8; piece];
+            loop {
+                let n = reader.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                out.extend_from_slice(&buf[..n]);
+            }
+            assert!(out == data, "{}", piece);
+        }
+    }
+
+    #[test]
+    fn io_same_as_chunks() {
+        let data = plain(CHUNK_LEN + 10);
+        let stored = encrypt(&data, data.len());
+
+        let mut chunks = [data[..CHUNK_LEN].to_vec(), data[CHUNK_LEN..].to_vec()];
+        let mut encryptor = StreamEncryptor::new(b"key", b"nonce");
+        let first = encryptor.encrypt_next(&mut chunks[0]).unwrap();
+        let last = encryptor.encrypt_last(&mut chunks[1]);
+        let expected = [&chunks[0][..], first.as_bytes(), &chunks[1][..], last.as_bytes()].concat();
+        assert!(stored == expected);
+    }
+
+    #[test]
+    fn io_truncated() {
+        let stored = encrypt(&plain(2 * CHUNK_LEN + 100), usize::MAX);
+        assert_rejected(&[]);
+        assert_rejected(&stored[..TAG_LEN - 1]);
+        /* Cut at a chunk boundary, and part way through chunks */
+        assert_rejected(&stored[..FRAME]);
+        assert_rejected(&stored[..2 * FRAME]);
+        assert_rejected(&stored[..FRAME + 1]);
+        assert_rejected(&stored[..stored.len() - 1]);
+    }
+
+    #[test]
+    fn io_reordered() {
+        let stored = encrypt(&plain(3 * CHUNK_LEN), usize::MAX);
+        let frames: Vec<&[u8]> = stored.chunks(FRAME).collect();
+        assert_eq!(frames.len(), 3);
+
+        assert_rejected(&[frames[1], frames[0], frames[2]].concat());
+        assert_rejected(&[frames[0], frames[0], frames[1], frames[2]].concat());
+        assert_rejected(&[frames[0], frames[2]].concat());
+        assert_rejected(&[frames[0], frames[1], frames[1], frames[2]].concat());
+    }
+
+    #[test]
+    fn io_appended() {
+        let stored = encrypt(&plain(CHUNK_LEN + 5), usize::MAX);
+        assert_rejected(&[&stored[..], b"x"].concat());
+        assert_rejected(&[&stored[..], &stored[..]].concat());
+    }
+
+    #[test]
+    fn io_tampered() {
+        let stored = encrypt(&plain(2 * CHUNK_LEN + 100), usize::MAX);
+        for &at in [0, CHUNK_LEN, FRAME, stored.len() - 1].iter() {
+            let mut changed = stored.clone();
+            changed[at] ^= 0x80;
+            assert_rejected(&changed);
+        }
+
+        assert!(decrypt(&stored, b"other key", b"nonce").is_err());
+        assert!(decrypt(&stored, b"key", b"other nonce").is_err());
+    }
+
+    #[test]
+    fn io_nothing_before_failure() {
+        /* The first chunk is given out, the changed second one isn't, and the error sticks */
+        let data = plain(2 * CHUNK_LEN + 100);
+        let mut stored = encrypt(&data, usize::MAX);
+        stored[FRAME + 10] ^= 1;
+
+        let mut reader = StreamReader::new(&stored[..], b"key", b"nonce");
+        let mut buf = vec![0u8; 2 * CHUNK_LEN];
+        assert_eq!(reader.read(&mut buf).unwrap(), CHUNK_LEN);
+        assert!(buf[..CHUNK_LEN] == data[..CHUNK_LEN]);
+        assert_eq!(reader.read(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.read(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn io_unfinished_writer() {
+        let data = plain(CHUNK_LEN + 100);
+        let mut stored = Vec::new();
+        {
+            let mut writer = StreamWriter::new(&mut stored, b"key", b"nonce");
+            writer.write_all(&data).unwrap();
+            writer.flush().unwrap();
+        }
+        /* Only the first chunk was written, and not as the last */
+        assert_eq!(stored.len(), FRAME);
+        assert_rejected(&stored);
+    }
+
+    /// Fails the first write, then takes everything.
+    struct FailOnce {
+        written: Vec<u8>,
+        fail: bool,
+    }
+
+    impl Write for FailOnce {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            if self.fail {
+                self.fail = false;
+                return Err(io::ErrorKind::StorageFull.into());
+            }
+            self.written.extend_from_slice(data);
+            Ok(data.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn io_failed_write() {
+        let data = plain(CHUNK_LEN + 100);
+        let mut writer = StreamWriter::new(FailOnce { written: Vec::new(), fail: true }, b"key", b"nonce");
+        writer.write_all(&data[..CHUNK_LEN]).unwrap();
+        /* The first chunk goes out when more data comes, and fails */
+        assert_eq!(writer.write(&data[CHUNK_LEN..]).unwrap_err().kind(), io::ErrorKind::StorageFull);
+
+        /* The inner writer would work now, but the stream stays broken */
+        assert!(writer.write(&data[CHUNK_LEN..]).is_err());
+        assert!(writer.write(b"").is_err());
+        assert!(writer.finish().is_err());
+    }
+
+    /// Hands out a byte at a time, with an interruption before each one.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::ErrorKind::Interrupted.into());
+            }
+            let n = self.data.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn io_short_reads() {
+        let data = plain(CHUNK_LEN + 100);
+        let stored = encrypt(&data, usize::MAX);
+        let mut out = Vec::new();
+        StreamReader::new(Trickle { data: &stored, interrupt: false }, b"key", b"nonce").read_to_end(&mut out).unwrap();
+        assert!(out == data);
+    }
+}
@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod tree {
+    use spritz_cipher::tree::{self, TreeHasher, TreeParams};
+    use spritz_cipher::{LengthEncoding, SpritzCipherError, SpritzHasher};
+
+    /// The tree hash straight from its definition, on the plain Spritz hash.
+    fn reference(params: TreeParams, digest: &mut [u8], data: &[u8]) {
+        fn chain(parts: &[&[u8]], domain: u8) -> [u8; 32] {
+            let mut hasher = SpritzHasher::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.update(&[domain]);
+            let mut cv = [0u8; 32];
+            hasher.finalize(&mut cv).unwrap();
+            cv
+        }
+
+        let leaves: Vec<&[u8]> = if data.is_empty() { vec![data] } else { data.chunks(params.leaf_size()).collect() };
+        let mut level: Vec<[u8; 32]> = leaves.iter().map(|leaf| chain(&[leaf], 0)).collect();
+        while level.len() > params.fan_out() {
+            level = level
+                .chunks(params.fan_out())
+                .map(|children| chain(&children.iter().map(|cv| &cv[..]).collect::<Vec<_>>(), 1))
+                .collect();
+        }
+
+        let mut hasher = SpritzHasher::new();
+        for cv in level.iter() {
+            hasher.update(cv);
+        }
+        hasher.update(&(params.leaf_size() as u64).to_le_bytes());
+        hasher.update(&(params.fan_out() as u64).to_le_bytes());
+        hasher.update(&[2]);
+        hasher.finalize_with(digest, LengthEncoding::Extended).unwrap();
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|n| (n * 7 + n / 256) as u8).collect()
+    }
+
+    /// Every way of computing the digest, checked against the reference.
+    fn check(params: TreeParams, len: usize) {
+        let data = data(len);
+        let mut expected = [0u8; 32];
+        reference(params, &mut expected, &data);
+
+        let mut digest = [0u8; 32];
+        tree::hash(params, &mut digest, &data).unwrap();
+        assert_eq!(digest, expected, "{:?}, {} bytes", params, len);
+
+        /* Byte by byte never takes the lanes, odd pieces sometimes do */
+        for piece in [1, 5, params.leaf_size() * 4 + 3] {
+            let mut hasher = TreeHasher::new(params);
+            for chunk in data.chunks(piece) {
+                hasher.update(chunk);
+            }
+            hasher.finalize(&mut digest).unwrap();
+            assert_eq!(digest, expected, "{:?}, {} bytes in {} byte pieces", params, len, piece);
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            tree::hash_parallel(params, &mut digest, &data).unwrap();
+            assert_eq!(digest, expected, "{:?}, {} bytes in parallel", params, len);
+        }
+    }
+
+    #[test]
+    fn matches_reference() {
+        for (leaf_size, fan_out) in [(1, 2), (7, 2), (16, 3), (64, 4), (100, 16)] {
+            let params = TreeParams::new(leaf_size, fan_out).unwrap();
+            /* Around one leaf, one full node and one full level of nodes */
+            let mut lens = vec![0, 1, 1000];
+            for leaves in [1, fan_out, fan_out * fan_out, fan_out * fan_out * fan_out] {
+                let len = leaves * leaf_size;
+                lens.extend([len - 1, len, len + 1]);
+            }
+            for len in lens {
+                check(params, len);
+            }
+        }
+    }
+
+    #[test]
+    fn default_params() {
+        assert_eq!(TreeParams::default(), TreeParams::new(65536, 16).unwrap());
+        check(TreeParams::DEFAULT, 65536 * 5 + 100);
+    }
+
+    #[test]
+    fn huge_leaves() {
+        /* Four leaves don't fit in a usize, the whole input is one short leaf */
+        let params = TreeParams::new(usize::MAX / 2, 2).unwrap();
+        let data = data(1000);
+        let mut expected = [0u8; 32];
+        reference(params, &mut expected, &data);
+
+        let mut digest = [0u8; 32];
+        tree::hash(params, &mut digest, &data).unwrap();
+        assert_eq!(digest, expected);
+
+        #[cfg(feature = "parallel")]
+        {
+            tree::hash_parallel(params, &mut digest, &data).unwrap();
+            assert_eq!(digest, expected);
+        }
+    }
+
+    #[test]
+    fn parameters_are_hashed() {
+        let data = data(500);
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        let mut c = [0u8; 32];
+        tree::hash(TreeParams::new(1000, 2).unwrap(), &mut a, &data).unwrap();
+        tree::hash(TreeParams::new(1000, 3).unwrap(), &mut b, &data).unwrap();
+        tree::hash(TreeParams::new(600, 2).unwrap(), &mut c, &data).unwrap();
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+
+        let mut plain = [0u8; 32];
+        SpritzHasher::hash(&mut plain, &data).unwrap();
+        assert_ne!(a, plain);
+    }
+
+    #[test]
+    fn long_digests() {
+        let params = TreeParams::new(10, 2).unwrap();
+        let mut digest = [0u8; 300];
+        assert_eq!(
+            tree::hash(params, &mut digest, b"data"),
+            Err(SpritzCipherError::DigestTooLong { max: 255, actual: 300 })
+        );
+
+        tree::hash_with(params, &mut digest, b"data", LengthEncoding::Extended).unwrap();
+        let mut expected = [0u8; 300];
+        reference(params, &mut expected, b"data");
+        assert_eq!(digest, expected);
+
+        #[cfg(feature = "parallel")]
+        {
+            assert!(tree::hash_parallel(params, &mut digest, b"data").is_err());
+            tree::hash_parallel_with(params, &mut digest, b"data", LengthEncoding::Extended).unwrap();
+            assert_eq!(digest, expected);
+        }
+    }
+
+    #[test]
+    fn bad_params() {
+        assert_eq!(TreeParams::new(0, 4), Err(SpritzCipherError::TooSmall { min: 1, actual: 0 }));
+        assert_eq!(TreeParams::new(1024, 1), Err(SpritzCipherError::TooSmall { min: 2, actual: 1 }));
+    }
+}
@@ -0,0 +1,114 @@
+#[cfg(test)]
+mod types {
+    use spritz_cipher::encoding::{Base64, Hex};
+    use spritz_cipher::{Digest, LengthEncoding, Nonce, SpritzCipher, SpritzCipherError, SpritzHasher};
+    use spritz_cipher::{SpritzKey, SpritzMac, SpritzRng, Tag};
+    use std::convert::TryFrom;
+    use zeroize::Zeroize;
+
+    #[test]
+    fn hex() {
+        let key = SpritzKey::<4>::from_hex("00ff7Fa0").unwrap();
+        assert_eq!(key.as_bytes(), &[0x00, 0xff, 0x7f, 0xa0]);
+        assert_eq!(key.to_hex().to_string(), "00ff7fa0");
+
+        assert_eq!(SpritzKey::<4>::from_hex("00ff7f").unwrap_err(), SpritzCipherError::InvalidLength { expected: 4, actual: 3 });
+        assert_eq!(SpritzKey::<4>::from_hex("00ff7g00").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 5 });
+        /* An odd digit count is a dangling digit, not a length that happens to round to the right one */
+        assert_eq!(SpritzKey::<4>::from_hex("00ff7fa00").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 8 });
+        assert_eq!(SpritzKey::<4>::from_hex("0").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 0 });
+
+        /* Every ASCII character against the obvious implementation */
+        for c in 0u8..128 {
+            let text: String = [c as char, '0'].iter().collect();
+            let expected = (c as char).to_digit(16).map(|d| (d as u8) << 4);
+            assert_eq!(Nonce::<1>::from_hex(&text).ok().map(|n| n.as_bytes()[0]), expected, "{:?}", c as char);
+        }
+    }
+
+    #[test]
+    fn base64() {
+        /* RFC 4648 section 10 */
+        assert_eq!(Base64(b"f").to_string(), "Zg==");
+        assert_eq!(Base64(b"fo").to_string(), "Zm8=");
+        assert_eq!(Base64(b"foo").to_string(), "Zm9v");
+        assert_eq!(Base64(b"foobar").to_string(), "Zm9vYmFy");
+        assert_eq!(Tag::<6>::from_base64("Zm9vYmFy").unwrap().as_bytes(), b"foobar");
+        assert_eq!(Tag::<2>::from_base64("Zm8=").unwrap().as_bytes(), b"fo");
+
+        assert_eq!(Tag::<2>::from_base64("Zm9v").unwrap_err(), SpritzCipherError::InvalidLength { expected: 2, actual: 3 });
+        assert_eq!(Tag::<2>::from_base64("Zm8").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 3 });
+        assert_eq!(Tag::<2>::from_base64("Z.8=").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 1 });
+        assert!(Tag::<1>::from_base64("Z===").is_err());
+
+        /* Only the encoding with zero bits before the padding is accepted */
+        assert_eq!(SpritzKey::<4>::from_base64("QUJDRA==").unwrap().as_bytes(), b"ABCD");
+        assert_eq!(SpritzKey::<4>::from_base64("QUJDRB==").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 5 });
+        assert_eq!(Tag::<2>::from_base64("Zm9=").unwrap_err(), SpritzCipherError::InvalidEncoding { position: 2 });
+
+        /* Every 6 bit value and every length round trips */
+        let all: Vec<u8> = (0..=255).collect();
+        let text = Base64(&all).to_string();
+        assert_eq!(Digest::<256>::from_base64(&text).unwrap().as_bytes()[..], all[..]);
+        assert!(text.contains('+') && text.contains('/'));
+
+        let mut rng = SpritzRng::new(b"base64");
+        let key = SpritzKey::<7>::generate(&mut rng);
+        assert_eq!(SpritzKey::<7>::from_base64(&key.to_base64().to_string()).unwrap(), key);
+        assert_eq!(SpritzKey::<7>::from_hex(&Hex(key.as_bytes()).to_string()).unwrap(), key);
+    }
+
+    #[test]
+    fn conversions() {
+        let key = SpritzKey::<3>::try_from(&[1u8, 2, 3][..]).unwrap();
+        assert_eq!(key, SpritzKey::from([1, 2, 3]));
+        assert_ne!(key, SpritzKey::new([1, 2, 4]));
+        assert_eq!(SpritzKey::<3>::try_from(&[1u8, 2][..]).unwrap_err(), SpritzCipherError::InvalidLength { expected: 3, actual: 2 });
+
+        assert_eq!(format!("{:?}", key), "SpritzKey<3>(..)");
+        assert_eq!(format!("{:?}", Tag::<32>::new([7; 32])), "Tag<32>(..)");
+
+        let mut key = key;
+        key.zeroize();
+        assert_eq!(key.as_bytes(), &[0, 0, 0]);
+    }
+
+    #[test]
+    fn typed_entry_points() {
+        let key: SpritzKey = SpritzKey::generate(&mut SpritzRng::new(b"key"));
+        let nonce: Nonce = Nonce::generate(&mut SpritzRng::new(b"nonce"));
+
+        let mut typed = *b"attack at dawn";
+        let mut slices = typed;
+        SpritzCipher::from_key_and_nonce(&key, &nonce).apply_keystream(&mut typed);
+        SpritzCipher::with_nonce(key.as_bytes(), nonce.as_bytes()).apply_keystream(&mut slices);
+        assert_eq!(typed, slices);
+
+        let mut expected = [0u8; 32];
+        SpritzHasher::hash(&mut expected, b"msg").unwrap();
+        assert_eq!(SpritzHasher::digest::<32>(b"msg").as_bytes(), &expected);
+
+        let mut expected = [0u8; 300];
+        SpritzHasher::hash_with(&mut expected, b"msg", LengthEncoding::Extended).unwrap();
+        assert_eq!(SpritzHasher::digest::<300>(b"msg").as_bytes()[..], expected[..]);
+
+        let mut expected = [0u8; 16];
+        SpritzMac::mac(&mut expected, b"msg", key.as_bytes()).unwrap();
+        let tag: Tag<16> = SpritzMac::tag(b"msg", &key);
+        assert_eq!(tag.as_bytes(), &expected);
+
+        let mut mac = SpritzMac::from_key(&key);
+        mac.update(b"msg");
+        assert!(mac.verify_tag(&tag));
+
+        let mut mac = SpritzMac::from_key(&key);
+        mac.update(b"msh");
+        assert!(!mac.verify_tag(&tag));
+
+        /* Correct but too short to trust */
+        let short: Tag<8> = SpritzMac::tag(b"msg", &key);
+        let mut mac = SpritzMac::from_key(&key);
+        mac.update(b"msg");
+        assert!(!mac.verify_tag(&short));
+    }
+}
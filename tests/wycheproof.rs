@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod wycheproof {
+    use serde_json::Value;
+    use spritz_cipher::{encoding, SpritzCipher, SpritzHasher, SpritzMac};
+    use std::process::Command;
+
+    const VECTORS: &str = include_str!("vectors/spritz_wycheproof.json");
+
+    fn hex(test: &Value, name: &str) -> Vec<u8> {
+        let text = test[name].as_str().unwrap_or_else(|| panic!("tcId {}: missing '{}'", test["tcId"], name));
+        let mut bytes = vec![0u8; text.len() / 2];
+        encoding::decode_hex(text, &mut bytes).unwrap_or_else(|e| panic!("tcId {}: bad '{}': {}", test["tcId"], name, e));
+        bytes
+    }
+
+    /// The digest or tag size of the group, in bytes.
+    fn size(group: &Value, name: &str) -> usize {
+        group[name].as_u64().unwrap() as usize / 8
+    }
+
+    /// True if the implementation agrees with the expected output.
+    fn matches(expected: &[u8], actual: &[u8]) -> bool {
+        spritz_cipher::ct::ct_eq(expected, actual)
+    }
+
+    fn run(group: &Value, test: &Value) -> bool {
+        match group["type"].as_str().unwrap() {
+            "SpritzHashTest" => {
+                let mut digest = vec![0u8; size(group, "digestSize")];
+                SpritzHasher::hash(&mut digest, &hex(test, "msg")).unwrap();
+                matches(&hex(test, "digest"), &digest)
+            }
+            "SpritzMacTest" => {
+                let mut tag = vec![0u8; size(group, "tagSize")];
+                let mut mac = SpritzMac::new(&hex(test, "key"));
+                mac.update(&hex(test, "msg"));
+                mac.finalize(&mut tag).unwrap();
+                matches(&hex(test, "tag"), &tag)
+            }
+            "SpritzStreamTest" => {
+                let msg = hex(test, "msg");
+                let ct = hex(test, "ct");
+                let mut out = vec![0u8; msg.len()];
+                let mut cipher = SpritzCipher::with_nonce(&hex(test, "key"), &hex(test, "iv"));
+                cipher.crypt(&msg, &mut out).unwrap();
+
+                /* Decrypting must round trip as well */
+                let mut back = vec![0u8; ct.len()];
+                let mut cipher = SpritzCipher::with_nonce(&hex(test, "key"), &hex(test, "iv"));
+                cipher.crypt(&ct, &mut back).unwrap();
+
+                matches(&ct, &out) && matches(&msg, &back)
+            }
+            kind => panic!("unknown test group type '{}'", kind),
+        }
+    }
+
+    #[test]
+    fn vectors() {
+        let root: Value = serde_json::from_str(VECTORS).unwrap();
+        assert_eq!(root["algorithm"], "SPRITZ");
+
+        let mut count = 0;
+        for group in root["testGroups"].as_array().unwrap() {
+            for test in group["tests"].as_array().unwrap() {
+                let valid = match test["result"].as_str().unwrap() {
+                    "valid" => true,
+                    "invalid" => false,
+                    result => panic!("tcId {}: unknown result '{}'", test["tcId"], result),
+                };
+                for flag in test["flags"].as_array().unwrap() {
+                    assert!(root["notes"][flag.as_str().unwrap()].is_string(), "tcId {}: undocumented flag {}", test["tcId"], flag);
+                }
+
+                assert_eq!(run(group, test), valid, "tcId {}: {}", test["tcId"], test["comment"]);
+                count += 1;
+            }
+        }
+
+        assert_eq!(root["numberOfTests"], count);
+    }
+
+    #[test]
+    fn generator_is_up_to_date() {
+        let out = Command::new(env!("CARGO_BIN_EXE_spritz-wycheproof")).output().unwrap();
+        assert!(out.status.success());
+        assert!(
+            String::from_utf8(out.stdout).unwrap() == VECTORS,
+            "tests/vectors/spritz_wycheproof.json is stale, regenerate it with `cargo run --bin spritz-wycheproof`"
+        );
+    }
+}